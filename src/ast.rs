@@ -1,38 +1,335 @@
 use super::token::Token;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, ops::RangeInclusive, rc::Rc};
 
 /// The base building blocks of the language
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Stmt {
     Expr(Expr),
     Print(Expr),
-    Block(Vec<Stmt>),
-    Var { name: Token, value: Option<Expr> },
+    Block(Vec<Self>),
+    Var {
+        name: Token,
+        value: Option<Expr>,
+    },
+    /// `var (a, b) = value;`, declaring a variable for each element of `value`
+    VarTuple {
+        names: Vec<Token>,
+        value: Expr,
+    },
+    /// A function declaration, along with the decorators that are applied to it, innermost last
+    Function {
+        decorators: Vec<Expr>,
+        decl: Rc<FunDecl>,
+    },
+    /// A class declaration, calling which creates instances that have its methods
+    Class {
+        name: Token,
+        /// the `///` comments preceding the declaration
+        doc: Option<String>,
+        methods: Vec<Rc<FunDecl>>,
+        /// the methods declared `static`, which are called on the class rather than an instance
+        statics: Vec<Rc<FunDecl>>,
+        /// `get name()` methods, called when the property `name` of an instance is read
+        getters: Vec<Rc<FunDecl>>,
+        /// `set name(value)` methods, called when the property `name` of an instance is assigned
+        setters: Vec<Rc<FunDecl>>,
+        /// the traits that the class declares it implements, whose methods it must have
+        traits: Vec<Token>,
+    },
+    /// A trait declaration, listing the methods that classes implementing it must have
+    Trait {
+        name: Token,
+        /// the `///` comments preceding the declaration
+        doc: Option<String>,
+        methods: Vec<Signature>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    /// An expression evaluated once the enclosing block is exited
+    Defer(Expr),
+    /// A resource bound to `name` for the duration of `body`, disposed of once it is exited
+    With {
+        keyword: Token,
+        name: Token,
+        value: Expr,
+        body: Vec<Self>,
+    },
+    /// Raises `value` as an error, which `catch` receives unchanged
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    /// Statements whose first error is caught, running `handler` with a description of the error
+    /// bound to `name`
+    Try {
+        body: Vec<Self>,
+        name: Token,
+        handler: Vec<Self>,
+    },
+    /// Executes the body of the first arm whose pattern matches `value`, if any do
+    Match {
+        keyword: Token,
+        value: Expr,
+        arms: Vec<Arm>,
+    },
+    /// A statement executed for as long as `condition` is true
+    While {
+        keyword: Token,
+        condition: Expr,
+        body: Box<Self>,
+    },
+    /// `body` executed once for each element of `iterable`, which is bound to `name`
+    ForIn {
+        keyword: Token,
+        name: Token,
+        iterable: Expr,
+        body: Vec<Self>,
+    },
+    /// A statement executed once, then again for as long as `condition` is true
+    DoWhile {
+        body: Box<Self>,
+        keyword: Token,
+        condition: Expr,
+    },
+}
+
+/// A `pattern => body` arm of a `match`
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub body: Stmt,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Pattern {
+    /// matches values equal to the literal, as if compared with `==`
+    Literal(Token),
+    /// `_`, which matches any value
+    Wildcard(Token),
+    /// `"prefix" ...rest`, which matches strings starting with the prefix, binding `rest` to the
+    /// remainder of the string in the arm's body
+    Prefix { prefix: Token, rest: Token },
+    /// `...rest "suffix"`, which matches strings ending with the suffix, binding `rest` to the
+    /// start of the string in the arm's body
+    Suffix { rest: Token, suffix: Token },
+    /// `regex "pattern"`, which matches strings that the regular expression matches somewhere
+    /// in, binding each of its named groups in the arm's body
+    Regex { keyword: Token, pattern: Token },
+}
+
+/// The parts of a function declaration that are shared by every value created from it
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct FunDecl {
+    pub name: Token,
+    /// the `///` comments preceding the declaration
+    pub doc: Option<String>,
+    pub params: Vec<Token>,
+    /// the default values of the last `defaults.len()` parameters, used when they are not passed
+    pub defaults: Vec<Expr>,
+    /// the `...rest` parameter, which collects the arguments left over into a list
+    pub rest: Option<Token>,
+    pub body: Vec<Stmt>,
+}
+
+impl FunDecl {
+    /// The numbers of arguments that the function can be called with
+    pub fn arity(&self) -> RangeInclusive<usize> {
+        let max = if self.rest.is_some() {
+            usize::MAX
+        } else {
+            self.params.len()
+        };
+        self.required()..=max
+    }
+
+    /// The number of parameters without a default value
+    pub fn required(&self) -> usize {
+        self.params.len() - self.defaults.len()
+    }
+
+    /// The default value of the parameter at `idx`, if it has one
+    pub fn default(&self, idx: usize) -> Option<&Expr> {
+        self.defaults.get(idx.checked_sub(self.required())?)
+    }
+
+    /// The parameters as they are written in the declaration, separated by commas
+    pub fn signature(&self) -> String {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| {
+                self.default(idx)
+                    .map_or_else(|| param.lex(), |default| format!("{param} = {default}"))
+            })
+            .chain(self.rest.iter().map(|rest| format!("...{}", rest.lex())))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A method that a trait requires, without a body
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Signature {
+    pub kind: MethodKind,
+    pub name: Token,
+    pub params: Vec<Token>,
+}
+
+impl Signature {
+    /// Whether one of `decls`, the methods of the signature's kind in a class, has its name and
+    /// can be called with its parameters
+    pub fn is_met_by<'a>(&self, decls: impl IntoIterator<Item = &'a FunDecl>) -> bool {
+        decls.into_iter().any(|decl| {
+            decl.name.lex() == self.name.lex() && decl.arity().contains(&self.params.len())
+        })
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = self.params.iter().map(Token::lex).collect::<Vec<_>>();
+        let kind = match self.kind {
+            MethodKind::Method => "",
+            MethodKind::Static => "static ",
+            MethodKind::Getter => "get ",
+            MethodKind::Setter => "set ",
+        };
+        write!(f, "{kind}{}({})", self.name, params.join(", "))
+    }
+}
+
+/// The ways a method can be declared in a class, which a trait's signatures must match
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MethodKind {
+    Method,
+    Static,
+    Getter,
+    Setter,
 }
 
 /// An expression: something that can be evaluated to produce a side effect
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Expr {
-    Binary(Box<Expr>, Token, Box<Expr>),
-    Grouping(Box<Expr>),
+    Binary(Box<Self>, Token, Box<Self>),
+    /// `and` or `or`, whose right operand is only evaluated if it decides the result
+    Logical(Box<Self>, Token, Box<Self>),
+    Grouping(Box<Self>),
     Literal(Token),
-    Unary(Token, Box<Expr>),
-    Assignment(Token, Box<Expr>),
+    Unary(Token, Box<Self>),
+    Assignment(Token, Box<Self>),
     Variable(Token),
+    /// the instance that the method being executed is bound to
+    This(Token),
+    /// `set{a, b}`, the `set` keyword followed by the elements
+    SetLiteral(Token, Vec<Self>),
+    /// `(a, b)`, the opening paren followed by the elements
+    Tuple(Token, Vec<Self>),
+    /// `(a, b) = value`, assigning each element of `value` to the variable in the same position
+    Destructure(Vec<Token>, Box<Self>),
+    /// callee, closing paren of the arguments, positional arguments, `name: value` arguments
+    Call(Box<Self>, Token, Vec<Self>, Vec<(Token, Self)>),
+    /// object, name of the property
+    Get(Box<Self>, Token),
+    /// object, name of the property, value assigned to it
+    Set(Box<Self>, Token, Box<Self>),
+    /// indexed value, closing bracket of the index, index
+    Index(Box<Self>, Token, Box<Self>),
+    Quote(Box<Quoted>),
+    /// `unquote` keyword, expression whose value is spliced into the surrounding quote
+    Unquote(Token, Box<Self>),
+}
+
+/// A fragment of syntax tree created by `quote`, allowing it to be held as a value
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Quoted {
+    Expr(Expr),
+    Stmts(Vec<Stmt>),
+}
+
+impl Expr {
+    /// The leftmost token of the expression, which is where it starts in source code
+    pub fn first_token(&self) -> Option<&Token> {
+        match self {
+            Self::Binary(expr, ..)
+            | Self::Logical(expr, ..)
+            | Self::Grouping(expr)
+            | Self::Call(expr, ..)
+            | Self::Get(expr, _)
+            | Self::Set(expr, ..)
+            | Self::Index(expr, ..) => expr.first_token(),
+            Self::Literal(tok)
+            | Self::Unary(tok, _)
+            | Self::Assignment(tok, _)
+            | Self::Variable(tok)
+            | Self::This(tok)
+            | Self::SetLiteral(tok, _)
+            | Self::Tuple(tok, _)
+            | Self::Unquote(tok, _) => Some(tok),
+            Self::Destructure(names, _) => names.first(),
+            Self::Quote(quoted) => match quoted.as_ref() {
+                Quoted::Expr(expr) => expr.first_token(),
+                Quoted::Stmts(_) => None,
+            },
+        }
+    }
 }
 
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Binary(left, op, right) => {
+            Self::Binary(left, op, right) | Self::Logical(left, op, right) => {
                 write!(f, "({left} {op} {right})")
             }
             Self::Unary(op, expr) => write!(f, "({op}{expr})"),
             Self::Literal(lit) => write!(f, "{lit}"),
             Self::Grouping(expr) => write!(f, "[{expr}]"),
-            Self::Variable(tok) => write!(f, "{tok}"),
+            Self::Variable(tok) | Self::This(tok) => write!(f, "{tok}"),
             Self::Assignment(tok, expr) => write!(f, "{expr} -> {tok}"),
+            Self::Call(callee, _, args, named) => {
+                write!(f, "{callee}(")?;
+                let named = named.iter().map(|(name, arg)| format!("{name}: {arg}"));
+                for (idx, arg) in args
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(named)
+                    .enumerate()
+                {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+            Self::Get(object, name) => write!(f, "{object}.{name}"),
+            Self::Set(object, name, value) => write!(f, "{value} -> {object}.{name}"),
+            Self::Index(indexed, _, index) => write!(f, "{indexed}[{index}]"),
+            Self::SetLiteral(_, elements) => write!(f, "set{{{}}}", joined(elements)),
+            Self::Tuple(_, elements) => write!(f, "({})", joined(elements)),
+            Self::Destructure(names, value) => write!(f, "{value} -> ({})", joined(names)),
+            Self::Quote(quoted) => write!(f, "quote {{ {quoted} }}"),
+            Self::Unquote(_, expr) => write!(f, "unquote({expr})"),
+        }
+    }
+}
+
+/// Displays each of `items`, separated by commas
+fn joined<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Quoted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expr(expr) => write!(f, "{expr}"),
+            Self::Stmts(stmts) => write!(f, "{} statements", stmts.len()),
         }
     }
 }
@@ -1,5 +1,12 @@
-use crate::{interpreter::Interpreter, lexer::Cursor, parser::Parser};
-use std::marker::PhantomData;
+use crate::{
+    environment::Env,
+    error::PathStyle,
+    interpreter::{Interpreter, NativeFn},
+    lexer::Cursor,
+    parser::Parser,
+    token::Value,
+};
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use color_eyre::{Report, Result};
 
@@ -9,6 +16,82 @@ pub struct Interpreting;
 
 type AResult<'a, T> = Result<Atium<'a, T>, Vec<Report>>;
 
+/// Bounds on the size of a program, so that hosts running untrusted source cannot have their
+/// memory exhausted while it is lexed and parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// the most tokens that the source may contain
+    pub tokens: usize,
+    /// the most expressions and statements that the syntax tree may contain
+    pub nodes: usize,
+    /// how deeply expressions and statements may be nested inside of one another
+    pub depth: usize,
+}
+
+/// Settings for how programs are run, which hosts embedding atium can tune
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// the number of characters after which printed values are cut off, preventing huge values
+    /// from flooding a terminal or host UI
+    pub max_print: Option<usize>,
+    /// whether `+` with exactly one string operand converts the other operand to a string, as
+    /// `str` would. Otherwise it is an error, which suggests calling `str`.
+    pub coerce_strings: bool,
+    /// whether the value of each top level expression statement is printed, as the REPL does.
+    /// Scripts leave it unset, so that evaluating an expression for its side effects is silent.
+    pub echo: bool,
+    /// source evaluated into the global scope before the program, defining helpers for it to
+    /// use. Its diagnostics are attributed to the file `<prelude>`.
+    pub prelude: Option<String>,
+    /// functions implemented by the host, which are defined in the global scope alongside the
+    /// built in functions
+    pub natives: Vec<NativeFn>,
+    /// bounds on the size of programs, which are unbounded if there are none
+    pub parse_limits: Option<ParseLimits>,
+    /// the most calls to functions declared in atium that may be in progress at once, past which
+    /// the program errors rather than overflowing the host's stack. Calls in tail position do
+    /// not count towards it.
+    pub max_depth: Option<usize>,
+    /// where `print` and natives such as `help` write their output
+    pub output: Output,
+    /// the resources of the host that programs may access, none by default
+    pub capabilities: Capabilities,
+    /// how diagnostics show the paths of source files, relative to the working directory when
+    /// the options were created by default
+    pub paths: PathStyle,
+}
+
+/// Access to the host that natives need to be granted before programs can call them, so that
+/// hosts running untrusted programs can keep them sandboxed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// whether files may be read and written, as `readCsv` and `writeCsv` do
+    pub fs: bool,
+}
+
+/// A destination for the output of programs
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    #[default]
+    Stdout,
+    /// collects the output, so that hosts can show it however they like
+    Buffer(Rc<RefCell<String>>),
+}
+
+impl Output {
+    /// Writes `text` followed by a newline
+    pub fn writeln(&self, text: &str) {
+        match self {
+            Self::Stdout => println!("{text}"),
+            Self::Buffer(buf) => {
+                let mut buf = buf.borrow_mut();
+                buf.push_str(text);
+                buf.push('\n');
+            }
+        }
+    }
+}
+
 /// Internal logic for the language
 pub struct Atium<'a, State = Lexing> {
     /// cursor that travrses source code and tokenises it
@@ -17,19 +100,47 @@ pub struct Atium<'a, State = Lexing> {
     parser: Parser,
     /// interpreter that walks AST, computing it
     interpeter: Interpreter,
+    /// global scope that the program is executed in
+    env: Rc<RefCell<Env>>,
+    options: Options,
+    /// whether the global scope was created for this program, rather than given to it, in which
+    /// case the prelude has yet to be evaluated into it
+    fresh_env: bool,
     /// state of the program
     state: PhantomData<State>,
 }
 
 impl<'a> Atium<'a> {
     pub fn new(src: &'a str, file: Option<&str>) -> Self {
+        Self {
+            fresh_env: true,
+            ..Self::with_env(src, file, Rc::new(RefCell::new(Env::global())))
+        }
+    }
+
+    /// Executes the program in an existing global scope, so that definitions made by previously
+    /// run programs are visible and new definitions outlive this program
+    ///
+    /// Neither the prelude nor the natives of the options are added to `env`, which should be
+    /// created by [`global_env`] if they are wanted.
+    pub fn with_env(src: &'a str, file: Option<&str>, env: Rc<RefCell<Env>>) -> Self {
         Self {
             cursor: Cursor::new(src, file),
             parser: Parser::new(Vec::default()), // NOTE: should not be used until State = Parsing
             interpeter: Interpreter::new(Vec::default()), // NOTE: don't use if State != Interpret
+            env,
+            options: Options::default(),
+            fresh_env: false,
             state: PhantomData::<Lexing>,
         }
     }
+
+    /// Runs the program with `options` rather than the defaults
+    #[must_use]
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 impl<State> Atium<'_, State> {
@@ -44,11 +155,18 @@ impl<State> Atium<'_, State> {
 
 impl<'a> Atium<'a, Lexing> {
     pub fn lex(self) -> AResult<'a, Parsing> {
-        self.cursor.lex().map(|ok| Atium {
+        let limits = self.options.parse_limits;
+        let cursor = self
+            .cursor
+            .with_max_tokens(limits.map(|limits| limits.tokens));
+        cursor.lex().map(|ok| Atium {
             state: PhantomData::<Parsing>,
-            parser: Parser::new(ok),
+            parser: Parser::new(ok).with_limits(limits),
             cursor: Cursor::new::<&str>("", None),
             interpeter: Interpreter::new(vec![]),
+            env: self.env,
+            options: self.options,
+            fresh_env: self.fresh_env,
         })
     }
 }
@@ -57,15 +175,63 @@ impl<'a> Atium<'a, Parsing> {
     pub fn parse(mut self) -> AResult<'a, Interpreting> {
         self.parser.parse().map(|ok| Atium {
             state: PhantomData::<Interpreting>,
-            interpeter: Interpreter::new(ok),
+            interpeter: Interpreter::with_env(ok, Rc::clone(&self.env))
+                .with_options(self.options.clone()),
             parser: Parser::new(vec![]),
             cursor: Cursor::new::<&str>("", None),
+            env: self.env,
+            options: self.options,
+            fresh_env: self.fresh_env,
         })
     }
 }
 
-impl<'a> Atium<'a, Interpreting> {
+impl Atium<'_, Interpreting> {
     pub fn interpret(self) -> Result<(), Vec<Report>> {
+        if self.fresh_env {
+            prepare(&self.env, &self.options)?;
+        }
         self.interpeter.interpret()
     }
+
+    /// Runs the program like [`Atium::interpret`], producing the value of its final statement if
+    /// it is an expression statement, and `nil` otherwise
+    pub fn evaluate(self) -> Result<Value, Vec<Report>> {
+        if self.fresh_env {
+            prepare(&self.env, &self.options)?;
+        }
+        self.interpeter.evaluate()
+    }
+}
+
+/// Creates a global scope that programs can share, containing the natives of `options`, into
+/// which its prelude has been evaluated
+pub fn global_env(options: &Options) -> Result<Rc<RefCell<Env>>, Vec<Report>> {
+    let env = Rc::new(RefCell::new(Env::global()));
+    prepare(&env, options)?;
+    Ok(env)
+}
+
+/// Defines the natives of `options` in `env`, then evaluates its prelude, if there is one, into it
+fn prepare(env: &Rc<RefCell<Env>>, options: &Options) -> Result<(), Vec<Report>> {
+    for native in &options.natives {
+        env.borrow_mut().define_native(native.clone());
+    }
+
+    let Some(prelude) = &options.prelude else {
+        return Ok(());
+    };
+    // the prelude is not a program the user wrote, so its values are never echoed
+    let options = Options {
+        echo: false,
+        prelude: None,
+        natives: vec![],
+        ..options.clone()
+    };
+
+    Atium::with_env(prelude, Some("<prelude>"), Rc::clone(env))
+        .with_options(options)
+        .lex()?
+        .parse()?
+        .interpret()
 }
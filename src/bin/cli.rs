@@ -1,15 +1,60 @@
-use atium::cli::{run_file, run_repl, Cli};
+use atium::atium::{Capabilities, Options};
+use atium::cli::{init_script, print_grammar, run_files, run_repl, run_tests, Cli, Command};
+use atium::error::PathStyle;
+use atium::kernel;
 use clap::Parser;
 use color_eyre::Result;
+use std::{
+    io::{stdin, stdout},
+    panic, thread,
+};
+
+/// Calls recurse on the stack, so programs run on a thread with room for `--max-depth` of them
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
 
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)?
+        .join()
+        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
-    if let Some(file) = cli.script {
-        run_file(&file)?;
+    // the settings that every mode shares, which each overrides as it needs
+    let options = Options {
+        max_print: cli.max_print,
+        coerce_strings: cli.coerce_strings,
+        max_depth: Some(cli.max_depth),
+        capabilities: Capabilities { fs: true },
+        paths: PathStyle {
+            absolute: cli.absolute_paths,
+            ..PathStyle::default()
+        },
+        ..Options::default()
+    };
+
+    if let Some(Command::Grammar { railroad, .. }) = cli.command {
+        print_grammar(railroad);
+    } else if let Some(Command::Test { files }) = cli.command {
+        if !run_tests(&files, &options)? {
+            std::process::exit(1);
+        }
+    } else if let Some(Command::Kernel) = cli.command {
+        kernel::serve(stdin().lock(), stdout().lock(), &options)?;
+    } else if cli.scripts.is_empty() {
+        let init = if cli.no_init { None } else { init_script() };
+        let options = Options {
+            max_print: cli.max_print.or(Some(10_000)),
+            echo: true,
+            ..options
+        };
+        run_repl(&cli.prompt, init, cli.session, &options)?;
     } else {
-        run_repl()?;
+        run_files(&cli.scripts, &options)?;
     }
 
     Ok(())
@@ -1,57 +1,383 @@
-use clap::Parser;
-use color_eyre::{eyre::Context, Report, Result};
+use clap::{Parser, Subcommand};
+use color_eyre::{
+    eyre::{eyre, Context},
+    Report, Result,
+};
 
-use crate::atium::Atium;
+use crate::{
+    atium::{Atium, Options, Output},
+    doctest, dump,
+    environment::Env,
+    error::{source_map::FileId, Column, Line, PathStyle, Span, SyntaxError},
+    grammar::{self, Notation},
+    reporter,
+};
 use std::{
-    fs::File,
-    io::{stdin, BufRead, BufReader, Read},
+    cell::RefCell,
+    env,
+    fs::{self, OpenOptions},
+    io::{stdin, stdout, Write},
+    path::PathBuf,
+    rc::Rc,
 };
 
 /// The outward facing CLI that handles command line input
 ///
 /// This CLI passes all input to [`Atium`] which handles the internal logic
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
+#[allow(clippy::struct_excessive_bools)] // each is an independent flag
 pub struct Cli {
-    pub script: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Scripts to run in order, sharing one global scope. Starts a REPL if none are given
+    pub scripts: Vec<String>,
     #[arg(long)]
     pub ast: bool,
+    /// Prompt shown by the REPL, in which `{line}` is replaced by the number of the line being
+    /// entered
+    #[arg(long, default_value = "> ")]
+    pub prompt: String,
+    /// Do not run the startup script when starting the REPL
+    #[arg(long)]
+    pub no_init: bool,
+    /// Cut off printed values after this many characters. Defaults to 10000 in the REPL and no
+    /// limit when running scripts
+    #[arg(long)]
+    pub max_print: Option<usize>,
+    /// Show the full paths of files in diagnostics, rather than paths relative to the working
+    /// directory
+    #[arg(long)]
+    pub absolute_paths: bool,
+    /// Let `+` concatenate a string with any other value, converting the value to a string
+    #[arg(long)]
+    pub coerce_strings: bool,
+    /// The most function calls that may be in progress at once, past which the program stops
+    /// with an error. Calls in tail position do not count
+    #[arg(long, default_value_t = 2_000)]
+    pub max_depth: usize,
+    /// File that the REPL's inputs are saved to. If it already exists, its inputs are run again
+    /// first to restore the environment of the previous session. Their output is hidden, but any
+    /// other side effects they have, such as writing files, happen again
+    #[arg(long)]
+    pub session: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Prints the grammar of the language
+    Grammar {
+        /// Write the grammar in ISO EBNF, the default
+        #[arg(long, group = "notation")]
+        ebnf: bool,
+        /// Write the grammar in the EBNF accepted by railroad diagram generators
+        #[arg(long, group = "notation")]
+        railroad: bool,
+    },
+    /// Checks that the examples in the documentation of each file's functions are correct
+    Test {
+        /// Files whose examples are checked, each in a global scope of its own
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Evaluates code cells sent as JSON lines on stdin, replying to each on stdout. See
+    /// `atium::kernel` for the protocol
+    Kernel,
+}
+
+/// Prints the grammar of the language in the chosen notation
+pub fn print_grammar(railroad: bool) {
+    let notation = if railroad {
+        Notation::Railroad
+    } else {
+        Notation::Ebnf
+    };
+    print!("{}", grammar::render(&grammar::grammar(), notation));
+}
+
+/// Runs each file, then checks the examples in the documentation of its functions against their
+/// expected values, printing the result of each. Returns whether all of them passed.
+///
+/// See [`doctest`] for how examples are written.
+pub fn run_tests<S: AsRef<str>>(files: &[S], options: &Options) -> Result<bool> {
+    let (mut passed, mut failed) = (0, 0);
+
+    for file in files {
+        let file = file.as_ref();
+        let env = Rc::new(RefCell::new(Env::global()));
+        let bytes = fs::read(file).wrap_err(format!("reading \"{file}\""))?;
+        // the file is run first, defining everything that its examples use
+        let examples = decode(&bytes, file)
+            .map_err(|err| vec![err])
+            .and_then(|src| {
+                run(&src, Some(file), Rc::clone(&env), options)?;
+                doctest::extract(&src, Some(file))
+            });
+        let examples = match examples {
+            Ok(examples) => examples,
+            Err(errs) => {
+                println!("{file} ... FAILED to run");
+                report(&errs, &options.paths);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for example in examples {
+            print!(
+                "{} ({}) >>> {} ... ",
+                example.function,
+                example.span.location(&options.paths),
+                example.source
+            );
+            let name = format!("<doctest {}>", example.function);
+            let value = Atium::with_env(&example.source, Some(&name), Rc::clone(&env))
+                .with_options(options.clone())
+                .lex()
+                .and_then(Atium::parse)
+                .and_then(Atium::evaluate);
+
+            match (value, &example.expected) {
+                (Ok(value), Some(expected)) if value.nested() != *expected => {
+                    let found = value.nested();
+                    println!("FAILED\n  expected: {expected}\n     found: {found}");
+                    failed += 1;
+                }
+                (Ok(_), _) => {
+                    println!("ok");
+                    passed += 1;
+                }
+                (Err(errs), _) => {
+                    println!("FAILED");
+                    report(&errs, &options.paths);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!("{passed} passed; {failed} failed");
+    Ok(failed == 0)
 }
 
 /// Reads source code from file
 pub fn run_file(file: &str) -> Result<()> {
-    let mut buf = String::default();
-    let f_handle = File::open(file).wrap_err(format!("reading \"{file}\""))?;
-    let mut f_handle = BufReader::new(f_handle);
-    f_handle.read_to_string(&mut buf)?;
+    run_files(&[file], &Options::default())
+}
+
+/// Reads and runs each file in order, with all of them sharing one global scope
+///
+/// Execution stops after the first file that produces errors, as later files are likely to depend
+/// on its definitions.
+pub fn run_files<S: AsRef<str>>(files: &[S], options: &Options) -> Result<()> {
+    let env = Rc::new(RefCell::new(Env::global()));
 
-    if let Err(errs) = run(&buf, Some(file)) {
-        report(&errs);
+    for file in files {
+        if !load(file.as_ref(), &env, options)? {
+            break;
+        }
     }
     Ok(())
 }
 
-fn report(errors: &[Report]) {
+/// Reads and runs `file` in `env`, reporting any errors it produces. Returns whether it ran
+/// successfully.
+fn load(file: &str, env: &Rc<RefCell<Env>>, options: &Options) -> Result<bool> {
+    let bytes = fs::read(file).wrap_err(format!("reading \"{file}\""))?;
+
+    let errs = match decode(&bytes, file) {
+        Ok(src) => run(&src, Some(file), Rc::clone(env), options).err(),
+        Err(err) => Some(vec![err]),
+    };
+    if let Some(errs) = errs {
+        report(&errs, &options.paths);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Decodes the raw contents of a source file into a string
+///
+/// A leading byte order mark is stripped; files starting with a UTF-16 BOM are transcoded to
+/// UTF-8. Malformed input produces a diagnostic pointing at the first offending byte instead of
+/// an io error.
+fn decode(bytes: &[u8], file: &str) -> Result<String, Report> {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => decode_utf8(rest, 3, file),
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, 2, file, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, 2, file, u16::from_be_bytes),
+        _ => decode_utf8(bytes, 0, file),
+    }
+}
+
+fn decode_utf8(bytes: &[u8], bom: usize, file: &str) -> Result<String, Report> {
+    match std::str::from_utf8(bytes) {
+        Ok(src) => Ok(src.to_string()),
+        Err(err) => {
+            let valid = std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap();
+            dump!(
+                SyntaxError::InvalidUtf8(bom + err.valid_up_to()),
+                span_at_end(valid, FileId::new(file, valid))
+            )
+        }
+    }
+}
+
+fn decode_utf16(
+    bytes: &[u8],
+    bom: usize,
+    file: &str,
+    unit: fn([u8; 2]) -> u16,
+) -> Result<String, Report> {
+    let units = bytes.chunks(2).map(|pair| match *pair {
+        [a, b] => unit([a, b]),
+        // a trailing odd byte can never be part of a valid code unit
+        _ => 0xDC00,
+    });
+
+    let mut src = String::new();
+    for (idx, c) in char::decode_utf16(units).enumerate() {
+        match c {
+            Ok(c) => src.push(c),
+            Err(_) => dump!(
+                SyntaxError::InvalidUtf16(bom + 2 * idx),
+                span_at_end(&src, FileId::new(file, &src))
+            ),
+        }
+    }
+    Ok(src)
+}
+
+/// Creates a span pointing just past the end of `src`, accounting for both LF and CRLF endings
+fn span_at_end(src: &str, file: FileId) -> Span {
+    let line = src.matches('\n').count() + 1;
+    let column = src.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    let end = u32::try_from(src.chars().count()).unwrap_or(u32::MAX);
+
+    Span {
+        line: Line(u32::try_from(line).unwrap_or(u32::MAX)),
+        column: Column(u32::try_from(column).unwrap_or(u32::MAX)),
+        start: end,
+        end,
+        file: Some(file),
+        lex: String::new(),
+    }
+}
+
+fn report(errors: &[Report], paths: &PathStyle) {
     for err in errors {
-        eprintln!("{err}");
+        eprintln!("{}", reporter::render(err, paths));
     }
 }
 
+/// The script run when the REPL starts, `$XDG_CONFIG_HOME/atium/init.at` falling back to
+/// `~/.config/atium/init.at`
+pub fn init_script() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config.join("atium").join("init.at"))
+}
+
 /// Reads source code line by line, as user enters it
-pub fn run_repl() -> Result<()> {
-    let mut input = stdin().lock();
+///
+/// `init` is run first if it exists, so that it can define helpers for the session. `prompt` is
+/// printed before each line, with `{line}` replaced by the number of that line. Entering `%n`
+/// re-runs the nth line.
+///
+/// Inputs are appended to `session`, if given, and the inputs already in it are replayed before
+/// any are read. Only their definitions are wanted, so what they print and the errors they
+/// report, which were seen when they were first entered, are not shown again.
+#[allow(clippy::literal_string_with_formatting_args)] // `{line}` is substituted by hand
+pub fn run_repl(
+    prompt: &str,
+    init: Option<PathBuf>,
+    session: Option<PathBuf>,
+    options: &Options,
+) -> Result<()> {
+    let env = Rc::new(RefCell::new(Env::global()));
+    if let Some(init) = init.filter(|init| init.is_file()) {
+        // the startup script is a file like any other, so it does not echo
+        let options = Options {
+            echo: false,
+            ..options.clone()
+        };
+        load(&init.to_string_lossy(), &env, &options)?;
+    }
+
+    let mut history = vec![];
+    if let Some(session) = session.as_ref().filter(|session| session.is_file()) {
+        let inputs =
+            fs::read_to_string(session).wrap_err(format!("reading \"{}\"", session.display()))?;
+        let quiet = Options {
+            echo: false,
+            output: Output::Buffer(Rc::default()),
+            ..options.clone()
+        };
+        for input in inputs.lines() {
+            let name = format!("repl:{}", history.len() + 1);
+            let _ = run(input, Some(&name), Rc::clone(&env), &quiet);
+            history.push(input.to_string());
+        }
+    }
+    let mut session = session
+        .map(|session| OpenOptions::new().create(true).append(true).open(session))
+        .transpose()?;
+
     let mut buf = String::new();
-    while input.read_line(&mut buf)? != 0 {
-        if let Err(errs) = run(&buf, None) {
-            report(&errs);
+    for line in history.len() + 1.. {
+        print!("{}", prompt.replace("{line}", &line.to_string()));
+        stdout().flush()?;
+
+        if stdin().read_line(&mut buf)? == 0 {
+            break;
+        }
+        let input = match expand(buf.trim_end(), &history) {
+            Ok(input) => {
+                // each input is a source of its own, that errors can point into
+                let name = format!("repl:{line}");
+                if let Err(errs) = run(&input, Some(&name), Rc::clone(&env), options) {
+                    report(&errs, &options.paths);
+                }
+                input
+            }
+            Err(err) => {
+                report(&[err], &options.paths);
+                // still takes up a line, so that later inputs keep their numbers
+                String::new()
+            }
+        };
+
+        if let Some(session) = &mut session {
+            writeln!(session, "{input}")?;
         }
+        history.push(input);
         buf.clear();
     }
     Ok(())
 }
 
-fn run(src: &str, file: Option<&str>) -> Result<(), Vec<Report>> {
-    let atium = Atium::new(src, file);
+/// Replaces an input of the form `%n` with the nth input of the session
+fn expand(input: &str, history: &[String]) -> Result<String> {
+    let Some(n) = input.trim().strip_prefix('%') else {
+        return Ok(input.to_string());
+    };
+
+    n.parse::<usize>()
+        .ok()
+        .and_then(|n| history.get(n.checked_sub(1)?))
+        .cloned()
+        .ok_or_else(|| eyre!("there is no input {n} to re-run"))
+}
+
+fn run(
+    src: &str,
+    file: Option<&str>,
+    env: Rc<RefCell<Env>>,
+    options: &Options,
+) -> Result<(), Vec<Report>> {
+    let atium = Atium::with_env(src, file, env).with_options(options.clone());
     atium.lex()?.parse()?.interpret()?;
     Ok(())
 }
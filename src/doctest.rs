@@ -0,0 +1,78 @@
+//! Examples written in the documentation of functions, which `atium test` checks are correct
+//!
+//! ```text
+//! /// Adds two numbers together
+//! ///
+//! /// >>> add(1, 2)
+//! /// 3
+//! fun add(a, b) { return a + b; }
+//! ```
+//!
+//! Each line starting with `>>>` is an example, which is evaluated in the global scope of the file
+//! it is written in. The lines that follow it, up to a blank line or the next example, are what
+//! the REPL is expected to echo for its value, so strings are quoted. An example without any is
+//! only expected to not error.
+use color_eyre::Report;
+
+use crate::{ast::Stmt, error::Span, lexer::Cursor, parser::Parser};
+
+/// An example from the documentation of a function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// the name of the function whose documentation the example is in
+    pub function: String,
+    /// where that function is declared
+    pub span: Span,
+    pub source: String,
+    /// what the REPL echoes for the value of `source`, if it is given
+    pub expected: Option<String>,
+}
+
+/// Parses `src`, producing the examples in the documentation of its top level functions
+pub fn extract(src: &str, file: Option<&str>) -> Result<Vec<Example>, Vec<Report>> {
+    let tokens = Cursor::new(src, file).lex()?;
+    let stmts = Parser::new(tokens).parse()?;
+    Ok(examples(&stmts))
+}
+
+/// The examples in the documentation of every function declared at the top level of `stmts`
+pub fn examples(stmts: &[Stmt]) -> Vec<Example> {
+    stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function { decl, .. } => Some(decl),
+            _ => None,
+        })
+        .flat_map(|decl| {
+            let doc = decl.doc.as_deref().unwrap_or_default();
+            parse(doc).into_iter().map(|(source, expected)| Example {
+                function: decl.name.lex(),
+                span: decl.name.span.clone(),
+                source,
+                expected,
+            })
+        })
+        .collect()
+}
+
+/// Splits `doc` into the source of each example and its expected output
+fn parse(doc: &str) -> Vec<(String, Option<String>)> {
+    let mut examples: Vec<(String, Option<String>)> = vec![];
+    // whether the lines being read are the expected output of the last example
+    let mut in_output = false;
+
+    for line in doc.lines().map(str::trim) {
+        if let Some(source) = line.strip_prefix(">>>") {
+            examples.push((source.trim().to_string(), None));
+            in_output = true;
+        } else if line.is_empty() {
+            in_output = false;
+        } else if let Some((_, expected)) = examples.last_mut().filter(|_| in_output) {
+            *expected = Some(expected.take().map_or_else(
+                || line.to_string(),
+                |expected| format!("{expected}\n{line}"),
+            ));
+        }
+    }
+    examples
+}
@@ -3,13 +3,14 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use crate::{
     dump,
     error::RuntimeError,
+    interpreter::{define_natives, ident, NativeFn},
     token::{Token, Value},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Env {
     env: HashMap<Token, Option<Rc<RefCell<Value>>>>,
-    parent: Option<RefCell<Box<Env>>>,
+    parent: Option<Rc<RefCell<Self>>>,
 }
 
 impl Env {
@@ -20,12 +21,32 @@ impl Env {
         }
     }
 
+    /// Creates the outermost scope of a program, which contains all of the native functions
+    pub fn global() -> Self {
+        let mut env = Self::new();
+        define_natives(&mut env);
+        env
+    }
+
+    /// Creates a new scope nested inside of `parent`
+    pub fn with_parent(parent: Rc<RefCell<Self>>) -> Self {
+        Self {
+            env: HashMap::new(),
+            parent: Some(parent),
+        }
+    }
+
     /// Defines a new variable using an identifier as its name
     pub fn define(&mut self, ident: Token, value: Option<Value>) {
         self.env
             .insert(ident, value.map(|inner| Rc::new(RefCell::new(inner))));
     }
 
+    /// Defines a function implemented in Rust, under its own name
+    pub fn define_native(&mut self, native: NativeFn) {
+        self.define(ident(&native.name), Some(Value::NativeFn(Rc::new(native))));
+    }
+
     /// Get a variable based on an identifier
     ///
     /// Keeps recursively checking outer scopes until it finds a variable or errors. Outer scopes
@@ -34,13 +55,14 @@ impl Env {
     /// The outer `Option` refers to whether the variable exists in the first place or not. The
     /// inner `Option` denotes whether the variable has an associated value or not.
     pub fn get(&self, ident: &Token) -> Option<Option<Value>> {
-        match self.env.get(ident) {
-            None => self
-                .parent
-                .as_ref()
-                .map_or(None, |inner| inner.borrow().get(ident)),
-            Some(val) => Some(val.as_ref().map(|x| x.borrow().clone())),
-        }
+        self.env.get(ident).map_or_else(
+            || {
+                self.parent
+                    .as_ref()
+                    .and_then(|inner| inner.borrow().get(ident))
+            },
+            |val| Some(val.as_ref().map(|x| x.borrow().clone())),
+        )
     }
 
     /// Assigns a value to a variable, overwriting the previous value.
@@ -50,19 +72,15 @@ impl Env {
     ///
     /// Errors if the assignment target is undefined
     pub fn assign(&mut self, ident: Token, value: Value) -> color_eyre::Result<Value> {
-        if self.env.contains_key(&ident) {
-            self.env
-                .insert(ident, Some(Rc::new(RefCell::new(value.clone()))));
+        if let Some(slot) = self.env.get_mut(&ident) {
+            *slot = Some(Rc::new(RefCell::new(value.clone())));
             Ok(value)
         } else {
+            let span = ident.span.clone();
             self.parent.as_ref().map_or_else(
-                || dump!(RuntimeError::InvalidAssignmentTarget::<String>),
+                || dump!(RuntimeError::InvalidAssignmentTarget::<String>, span),
                 |outer| outer.borrow_mut().assign(ident, value),
             )
         }
     }
-
-    pub fn set_parent(&mut self, parent: Env) {
-        self.parent = Some(RefCell::new(Box::new(parent)));
-    }
 }
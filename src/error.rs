@@ -1,21 +1,22 @@
+use color_eyre::Report;
 use colored::Colorize;
 use thiserror::Error;
 
-use std::{
-    error::Error,
-    fmt,
-    fmt::{Debug, Write},
-};
+use std::{error::Error, fmt, fmt::Debug, ops::RangeInclusive};
 
-pub use self::diagnostics::{Column, Line, Span};
+pub use self::diagnostics::{Column, Line, PathStyle, Span};
+use self::trace::{Frame, Traced};
 use crate::token::Type;
 
 pub mod diagnostics;
+pub mod source_map;
+pub mod trace;
 
+/// Creates a [`Diagnostic`] from an error kind and the span in source code that caused it
 #[macro_export]
-macro_rules! dump {
-    ($kind:expr) => {{
-        return Err($crate::error::Diagnostic {
+macro_rules! diagnostic {
+    ($kind:expr, $span:expr) => {
+        $crate::error::Diagnostic {
             kind: $kind,
             #[cfg(debug_assertions)]
             dbg_span: $crate::error::diagnostics::DbgSpan::new(
@@ -23,28 +24,47 @@ macro_rules! dump {
                 ::std::line!(),
                 ::std::column!(),
             ),
-            span: $crate::error::Span {
+            span: $span,
+            notes: ::std::vec::Vec::new(),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! dump {
+    ($kind:expr, $span:expr) => {{
+        return Err($crate::diagnostic!($kind, $span).into());
+    }};
+    ($kind:expr) => {{
+        return Err($crate::diagnostic!(
+            $kind,
+            $crate::error::Span {
                 line: $crate::error::Line(0),
                 column: $crate::error::Column(0),
+                start: 0,
+                end: 0,
                 file: None,
                 lex: String::new(),
-            }, // TODO: replace placeholder once Span is impl
-        }
+            } // TODO: replace placeholder once Span is impl
+        )
         .into());
     }};
 }
 
 /// Prints out the diagnostic in the format specified below:
 ///
+/// ```text
 /// error: no method `frobnicate` exists for `foo`
 ///     --> bar.as:26:4
 ///      |
 ///   26 | foo.frobnicate();
 ///      |     ^^^^^^^^^^ method doesn't exist
 ///      |
+/// ```
 ///
 /// Or if compiled with debug assertions:
 ///
+/// ```text
 /// [src/interpreter:63:12]:
 /// error: no method `frobnicate` exists for `foo`
 ///     --> bar.as:26:4
@@ -52,28 +72,75 @@ macro_rules! dump {
 ///   26 | foo.frobnicate();
 ///      |     ^^^^^^^^^^ method doesn't exist
 ///      |
-///
+/// ```
 impl<E> fmt::Display for Diagnostic<E>
 where
     E: Error,
 {
-    #[cfg(debug_assertions)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &PathStyle::VERBATIM)
+    }
+}
+
+impl<E> Diagnostic<E>
+where
+    E: Error,
+{
+    /// Writes the diagnostic, showing the paths of the files it points into in `style`
+    #[cfg(debug_assertions)]
+    fn write(&self, f: &mut fmt::Formatter<'_>, style: &PathStyle) -> fmt::Result {
         write!(
             f,
             "{} {}: {}",
             self.dbg_span,
             "error".red().bold(),
             self.kind.to_string().green()
-        )
+        )?;
+        if self.span.file.is_some() {
+            let location = self.span.location(style);
+            write!(f, "\n    {} {location}", "-->".blue().bold())?;
+        }
+        for (note, span) in &self.notes {
+            write!(f, "\n{}: {note}", "note".cyan().bold())?;
+            if span.file.is_some() {
+                write!(f, "\n    {} {}", "-->".blue().bold(), span.location(style))?;
+            }
+        }
+        Ok(())
     }
 
     #[cfg(not(debug_assertions))]
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fn write(&self, f: &mut fmt::Formatter<'_>, _: &PathStyle) -> fmt::Result {
         write!(f, "{:?}", &self)
     }
 }
 
+/// Displays `report`, showing the paths of the files it points into in `style`
+pub fn styled<'a>(report: &'a Report, style: &'a PathStyle) -> impl fmt::Display + 'a {
+    Styled(report, style)
+}
+
+struct Styled<'a>(&'a Report, &'a PathStyle);
+
+impl fmt::Display for Styled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(report, style) = self;
+        if let Some(diagnostic) = report.downcast_ref::<Diagnostic<SyntaxError>>() {
+            diagnostic.write(f, style)
+        } else if let Some(diagnostic) =
+            report.downcast_ref::<Diagnostic<RuntimeError<&'static str>>>()
+        {
+            diagnostic.write(f, style)
+        } else if let Some(diagnostic) = report.downcast_ref::<Diagnostic<RuntimeError<String>>>() {
+            diagnostic.write(f, style)
+        } else if let Some(diagnostic) = report.downcast_ref::<Diagnostic<RuntimeError<char>>>() {
+            diagnostic.write(f, style)
+        } else {
+            write!(f, "{report}")
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub struct Diagnostic<E>
 where
@@ -86,6 +153,17 @@ where
     pub dbg_span: self::diagnostics::DbgSpan,
     /// Information about where the error originates in _source code_
     pub span: Span,
+    /// Further places in source code that are relevant to the error
+    pub notes: Vec<(String, Span)>,
+}
+
+impl<E: Error> Diagnostic<E> {
+    /// Attaches a note about another place in source code
+    #[must_use]
+    pub fn note(mut self, note: impl Into<String>, span: Span) -> Self {
+        self.notes.push((note.into(), span));
+        self
+    }
 }
 
 /// Error that is generated during the lexing phase of the interpreter.
@@ -94,28 +172,97 @@ pub enum SyntaxError {
     #[error("an unexpected character was found while lexing: '{0}'")]
     UnexpectedCharacter(char),
 
+    #[error("{0} has too many digits to be a decimal")]
+    InvalidDecimal(String),
+
+    #[error("{0} is too large to be an integer")]
+    IntegerTooLarge(String),
+
+    #[error("decimal literals such as {0} need atium to be built with the decimal feature")]
+    DecimalsDisabled(String),
+
+    #[error("regex patterns such as {0} need atium to be built with the regex feature")]
+    RegexDisabled(String),
+
+    #[error("{0} is not a valid regular expression: {1}")]
+    InvalidRegex(String, String),
+
     #[error("expected '{expected}' but found '{found}'")]
     ExpectedCharacter { found: String, expected: char },
 
     #[error("expected identifier but found {0}")]
     ExpectedIdent(String),
 
+    #[error("expected a declaration but found {0}")]
+    ExpectedDeclaration(String),
+
+    #[error("expected an expression but found '{0}'")]
+    ExpectedExpression(String),
+
+    #[error("expected a pattern, either a literal or _, but found {0}")]
+    ExpectedPattern(String),
+
+    #[error("expected '{expected}' but found '{found}'")]
+    ExpectedKeyword {
+        found: String,
+        expected: &'static str,
+    },
+
+    #[error("the program is too large, it has more than {max} {what}")]
+    ProgramTooLarge { max: usize, what: &'static str },
+
+    #[error("{0} must have a default value, as it follows a parameter that has one")]
+    MissingDefault(String),
+
+    #[error("{0} must be named, as it follows an argument that is")]
+    PositionalAfterNamed(String),
+
+    #[error("return can only be used inside of a function")]
+    ReturnOutsideFunction,
+
+    #[error("init cannot return a value, calling a class always returns the new instance")]
+    ReturnFromInit,
+
+    #[error("getters take no parameters and setters take exactly one, unlike {0}")]
+    AccessorParameters(String),
+
+    #[error("this can only be used inside of a method that is not static")]
+    ThisOutsideClass,
+
+    #[error("{class} implements {r#trait} but has no method {method}")]
+    MissingTraitMethod {
+        class: String,
+        r#trait: String,
+        method: String,
+    },
+
     /// EOF was found in an unexpected place. don't know what was expected instead of it
     #[error("unexpected EOF found")]
     UnexpectedEOF,
+
+    /// The source is not valid UTF-8; holds the byte offset of the first invalid byte
+    #[error("source is not valid UTF-8, invalid byte found at offset {0}")]
+    InvalidUtf8(usize),
+
+    /// The source has a UTF-16 BOM but is not valid UTF-16; holds the offending byte offset
+    #[error("source is not valid UTF-16, invalid code unit found at offset {0}")]
+    InvalidUtf16(usize),
 }
 /// Error that is generated during interpretation.
 #[derive(Error, Debug)]
 pub enum RuntimeError<D: Debug> {
-    #[error("an invalid operator was used. found: {0} | expected: {}", display_vec(.1))]
-    InvalidOperator(String, Vec<D>),
+    #[error("'{0}' cannot be used as an operator here, expected {}", Alternatives(.1))]
+    InvalidOperator(String, Vec<&'static str>),
 
-    #[error("an invalid type was found: {0} | expected: {}", display_vec(.1))]
+    #[error("expected a value of type {} but found {0}", Alternatives(.1))]
     InvalidType(Type, Vec<Type>),
 
-    #[error("cannot apply '{0}' to values ({}), expected: {}", display_vec(.1), display_tuple_vec(.2))]
+    #[error("cannot apply '{0}' to {}, expected {}", Operands(.1), ExpectedOperands(.2))]
     InvalidTypes(D, Vec<Type>, Vec<(Type, Type)>),
 
+    #[error("cannot apply '{0}' to a value of type {1}, expected {}", Alternatives(.2))]
+    InvalidOperand(D, Type, Vec<Type>),
+
     #[error("invalid identifier, please define {0} before use")]
     InvalidIdent(D),
 
@@ -124,20 +271,328 @@ pub enum RuntimeError<D: Debug> {
 
     #[error("invalid assignment target")]
     InvalidAssignmentTarget,
+
+    #[error("unquote can only be used inside of a quote")]
+    UnquoteOutsideQuote,
+
+    #[error("{0} was not created by memoize")]
+    NotMemoized(String),
+
+    #[error("values of type {0} cannot be called")]
+    NotCallable(Type),
+
+    #[error("values of type {0} have no property {1}")]
+    UnknownProperty(Type, D),
+
+    #[error("values of type {0} cannot have properties set on them")]
+    NotSettable(Type),
+
+    #[error("{class} implements {r#trait} but has no method {method}")]
+    MissingTraitMethod {
+        class: String,
+        r#trait: String,
+        method: D,
+    },
+
+    #[error("a class can only implement traits, not values of type {0}")]
+    NotATrait(Type),
+
+    #[error("the right operand of is must be a class or trait, not a value of type {0}")]
+    NotAClassOrTrait(Type),
+
+    #[error("values of type {0} cannot be indexed")]
+    NotIndexable(Type),
+
+    #[error("values of type {0} cannot be iterated over, expected {}", Alternatives(.1))]
+    NotIterable(Type, Vec<Type>),
+
+    #[error("index {index} is out of bounds for a length of {len}")]
+    IndexOutOfBounds { index: i128, len: usize },
+
+    #[error("invalid template: {0}")]
+    InvalidTemplate(D),
+
+    #[error("cannot destructure {found} values into {expected} variables")]
+    DestructureMismatch { expected: usize, found: usize },
+
+    #[error("{0} is not a key of the map")]
+    MissingKey(String),
+
+    #[error("invalid argument to {0}: {1}")]
+    InvalidArgument(String, D),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("the result of '{0}' is too large to be an integer")]
+    IntegerOverflow(D),
+
+    #[error("the result of '{0}' is too large to be a decimal")]
+    DecimalOverflow(D),
+
+    #[error("cannot apply '{0}' to values measured in {1} and {2}")]
+    DimensionMismatch(D, String, String),
+
+    #[error("{function} needs the {capability} capability, which the host has not granted")]
+    CapabilityDenied {
+        function: String,
+        capability: &'static str,
+    },
+
+    #[error("\"{path}\": {message}")]
+    Io { path: String, message: String },
+
+    #[error("values of type {0} cannot be disposed of, they have no close or dispose method")]
+    NotDisposable(Type),
+
+    /// A value raised by `throw` that was never caught. The value itself is held by the
+    /// interpreter, at index `id` of its thrown values, so that `catch` can recover it
+    ///
+    /// `ty` is the class of an instance and the type of anything else, and `payload` is the
+    /// instance's `message` field if it has one.
+    #[error("uncaught {ty}: {payload}")]
+    Thrown {
+        ty: String,
+        payload: String,
+        id: usize,
+    },
+
+    #[error("{name} expects {} arguments but {found} were given", display_arity(.expected))]
+    ArityMismatch {
+        name: String,
+        expected: RangeInclusive<usize>,
+        found: usize,
+    },
+
+    #[error("{function} has no parameter named {name}")]
+    UnknownParameter { function: String, name: D },
+
+    #[error("{0} was given more than one argument")]
+    DuplicateArgument(D),
+
+    #[error("stack overflow, {depth} calls were already in progress")]
+    StackOverflow { depth: usize },
+
+    #[error("{function} was not given an argument for {param}")]
+    MissingArgument { function: String, param: D },
+}
+
+/// Errors that scripts can tell apart by name once they catch them
+trait Named {
+    fn name(&self) -> &'static str;
+
+    /// The index of the value that was thrown, if the error was raised by `throw`
+    fn thrown(&self) -> Option<usize> {
+        None
+    }
 }
 
-fn display_vec<T: fmt::Debug>(vec: &[T]) -> String {
-    let mut buffer = String::new();
-    write!(&mut buffer, "{vec:?}").unwrap();
-    String::from(&buffer[1..buffer.len() - 1])
+impl Named for SyntaxError {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::UnexpectedCharacter(_) => "UnexpectedCharacter",
+            Self::InvalidDecimal(_) => "InvalidDecimal",
+            Self::IntegerTooLarge(_) => "IntegerTooLarge",
+            Self::DecimalsDisabled(_) => "DecimalsDisabled",
+            Self::RegexDisabled(_) => "RegexDisabled",
+            Self::InvalidRegex(..) => "InvalidRegex",
+            Self::ExpectedCharacter { .. } => "ExpectedCharacter",
+            Self::ExpectedIdent(_) => "ExpectedIdent",
+            Self::ExpectedDeclaration(_) => "ExpectedDeclaration",
+            Self::ExpectedExpression(_) => "ExpectedExpression",
+            Self::ExpectedKeyword { .. } => "ExpectedKeyword",
+            Self::ExpectedPattern(_) => "ExpectedPattern",
+            Self::MissingDefault(_) => "MissingDefault",
+            Self::PositionalAfterNamed(_) => "PositionalAfterNamed",
+            Self::ProgramTooLarge { .. } => "ProgramTooLarge",
+            Self::ReturnOutsideFunction => "ReturnOutsideFunction",
+            Self::ReturnFromInit => "ReturnFromInit",
+            Self::ThisOutsideClass => "ThisOutsideClass",
+            Self::MissingTraitMethod { .. } => "MissingTraitMethod",
+            Self::AccessorParameters(_) => "AccessorParameters",
+            Self::UnexpectedEOF => "UnexpectedEOF",
+            Self::InvalidUtf8(_) => "InvalidUtf8",
+            Self::InvalidUtf16(_) => "InvalidUtf16",
+        }
+    }
 }
 
-fn display_tuple_vec<T: fmt::Debug>(vec: &[(T, T)]) -> String {
-    let mut buffer = format!("{:?}", vec[0]);
-    for tup in vec.iter().skip(1) {
-        buffer.push_str(&format!(" or {tup:?}"));
+impl<D: Debug> Named for RuntimeError<D> {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::InvalidOperator(..) => "InvalidOperator",
+            Self::InvalidType(..) => "InvalidType",
+            Self::InvalidTypes(..) => "InvalidTypes",
+            Self::InvalidOperand(..) => "InvalidOperand",
+            Self::InvalidIdent(_) => "InvalidIdent",
+            Self::UninitialisedVar(_) => "UninitialisedVar",
+            Self::InvalidAssignmentTarget => "InvalidAssignmentTarget",
+            Self::UnquoteOutsideQuote => "UnquoteOutsideQuote",
+            Self::NotMemoized(_) => "NotMemoized",
+            Self::NotCallable(_) => "NotCallable",
+            Self::UnknownProperty(..) => "UnknownProperty",
+            Self::NotSettable(_) => "NotSettable",
+            Self::MissingTraitMethod { .. } => "MissingTraitMethod",
+            Self::NotATrait(_) => "NotATrait",
+            Self::NotAClassOrTrait(_) => "NotAClassOrTrait",
+            Self::NotIndexable(_) => "NotIndexable",
+            Self::NotIterable(..) => "NotIterable",
+            Self::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+            Self::InvalidTemplate(_) => "InvalidTemplate",
+            Self::DestructureMismatch { .. } => "DestructureMismatch",
+            Self::MissingKey(_) => "MissingKey",
+            Self::InvalidArgument(..) => "InvalidArgument",
+            Self::DivisionByZero => "DivisionByZero",
+            Self::IntegerOverflow(_) => "IntegerOverflow",
+            Self::DecimalOverflow(_) => "DecimalOverflow",
+            Self::DimensionMismatch(..) => "DimensionMismatch",
+            Self::CapabilityDenied { .. } => "CapabilityDenied",
+            Self::Io { .. } => "Io",
+            Self::NotDisposable(_) => "NotDisposable",
+            Self::Thrown { .. } => "Thrown",
+            Self::ArityMismatch { .. } => "ArityMismatch",
+            Self::UnknownParameter { .. } => "UnknownParameter",
+            Self::DuplicateArgument(_) => "DuplicateArgument",
+            Self::StackOverflow { .. } => "StackOverflow",
+            Self::MissingArgument { .. } => "MissingArgument",
+        }
+    }
+
+    fn thrown(&self) -> Option<usize> {
+        match self {
+            Self::Thrown { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// The parts of an error that scripts can inspect once they catch it
+pub struct Described {
+    /// the name of the variant of the error, such as `DivisionByZero`
+    pub kind: &'static str,
+    pub message: String,
+    /// where the error occurred, if it is known
+    pub span: Option<Span>,
+    /// the calls that were in progress, innermost first
+    pub frames: Vec<Frame>,
+    /// see [`RuntimeError::Thrown`]
+    pub thrown: Option<usize>,
+}
+
+/// Breaks `report` down into the parts that scripts can inspect
+pub fn describe(report: &Report) -> Described {
+    if let Some(traced) = report.downcast_ref::<Traced>() {
+        return Described {
+            frames: traced.frames.clone(),
+            ..describe(&traced.error)
+        };
+    }
+    if let Some(error) = report.downcast_ref::<SyntaxError>() {
+        return Described {
+            kind: error.name(),
+            message: error.to_string(),
+            span: None,
+            frames: vec![],
+            thrown: None,
+        };
+    }
+
+    describe_diagnostic::<SyntaxError>(report)
+        .or_else(|| describe_diagnostic::<RuntimeError<&'static str>>(report))
+        .or_else(|| describe_diagnostic::<RuntimeError<String>>(report))
+        .or_else(|| describe_diagnostic::<RuntimeError<char>>(report))
+        .unwrap_or_else(|| Described {
+            kind: "Error",
+            message: report.to_string(),
+            span: None,
+            frames: vec![],
+            thrown: None,
+        })
+}
+
+fn describe_diagnostic<E>(report: &Report) -> Option<Described>
+where
+    E: Error + Named + Send + Sync + 'static,
+{
+    let diagnostic = report.downcast_ref::<Diagnostic<E>>()?;
+    Some(Described {
+        kind: diagnostic.kind.name(),
+        message: diagnostic.kind.to_string(),
+        span: Some(diagnostic.span.clone()),
+        frames: vec![],
+        thrown: diagnostic.kind.thrown(),
+    })
+}
+
+fn display_arity(arity: &RangeInclusive<usize>) -> String {
+    match (arity.start(), arity.end()) {
+        (start, end) if start == end => format!("{start}"),
+        (start, &usize::MAX) => format!("at least {start}"),
+        (start, end) => format!("{start} to {end}"),
+    }
+}
+
+/// Lists alternatives in prose, e.g. `Integer, Float, or String`
+struct Alternatives<'a, T>(&'a [T]);
+
+impl<T: fmt::Display> fmt::Display for Alternatives<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            [] => write!(f, "nothing"),
+            [only] => write!(f, "{only}"),
+            [first, second] => write!(f, "{first} or {second}"),
+            [rest @ .., last] => {
+                for item in rest {
+                    write!(f, "{item}, ")?;
+                }
+                write!(f, "or {last}")
+            }
+        }
+    }
+}
+
+/// The types of the operands an operator was applied to, e.g. `String and Integer`
+struct Operands<'a>(&'a [Type]);
+
+impl fmt::Display for Operands<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            [] => write!(f, "no values"),
+            [rest @ .., last] => {
+                for ty in rest {
+                    write!(f, "{ty} and ")?;
+                }
+                write!(f, "{last}")
+            }
+        }
+    }
+}
+
+/// The pairs of operand types that an operator accepts, e.g. `operands to be both Integer, or
+/// one Integer and one Float`
+///
+/// A pair that is accepted in either order is only described once.
+struct ExpectedOperands<'a>(&'a [(Type, Type)]);
+
+impl fmt::Display for ExpectedOperands<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(pairs) = self;
+        let mut described = vec![];
+        for (idx, (left, right)) in pairs.iter().enumerate() {
+            let swapped = (right.clone(), left.clone());
+            if left == right {
+                described.push(format!("both {left}"));
+            } else if pairs[..idx].contains(&swapped) {
+                // already described along with the pair it is swapped from
+            } else if pairs.contains(&swapped) {
+                described.push(format!("one {left} and one {right}"));
+            } else {
+                described.push(format!("{left} and {right}, in that order"));
+            }
+        }
+
+        write!(f, "operands to be {}", Alternatives(&described))
     }
-    buffer
 }
 
 impl fmt::Display for Type {
@@ -147,7 +602,21 @@ impl fmt::Display for Type {
             Self::Integer => write!(f, "Integer"),
             Self::Boolean => write!(f, "Boolean"),
             Self::Float => write!(f, "Float"),
+            #[cfg(feature = "decimal")]
+            Self::Decimal => write!(f, "Decimal"),
+            #[cfg(feature = "units")]
+            Self::Quantity => write!(f, "Quantity"),
             Self::Null => write!(f, "Null"),
+            Self::Function => write!(f, "Function"),
+            Self::Class => write!(f, "Class"),
+            Self::Trait => write!(f, "Trait"),
+            Self::Instance => write!(f, "Instance"),
+            Self::Ast => write!(f, "Ast"),
+            Self::List => write!(f, "List"),
+            Self::Range => write!(f, "Range"),
+            Self::Tuple => write!(f, "Tuple"),
+            Self::Map => write!(f, "Map"),
+            Self::Set => write!(f, "Set"),
         }
     }
 }
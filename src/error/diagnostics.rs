@@ -1,7 +1,13 @@
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    env,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use super::source_map::FileId;
 
 #[derive(Debug, Hash, Serialize, Deserialize, PartialEq, Clone, Eq)]
 pub struct Line(pub u32);
@@ -25,12 +31,103 @@ impl Display for Column {
 pub struct Span {
     pub line: Line,
     pub column: Column,
-    pub file: Option<PathBuf>,
+    /// the offset, in characters from the start of the source, of the first character of the
+    /// span
+    pub start: u32,
+    /// the offset just past the last character of the span, so two spans are adjacent when the
+    /// end of one is the start of the other
+    pub end: u32,
+    pub file: Option<FileId>,
     pub lex: String,
 }
 
 impl Span {
     pub fn to_snippet() {}
+
+    /// Displays the location of the span, e.g. `foo.at:3:14`, with the path of its file shown in
+    /// `style`
+    pub fn location<'a>(&'a self, style: &'a PathStyle) -> impl Display + 'a {
+        Location(self, style)
+    }
+}
+
+/// Displays the location of the span with the path of its file as it was given
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Location(self, &PathStyle::VERBATIM).fmt(f)
+    }
+}
+
+struct Location<'a>(&'a Span, &'a PathStyle);
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(span, style) = self;
+        if let Some(file) = &span.file {
+            write!(f, "{}:", style.show(file.name()))?;
+        }
+        write!(f, "{}:{}", span.line, span.column)
+    }
+}
+
+/// How diagnostics show the paths of source files
+///
+/// Paths are relative to `cwd`, unless they lie outside of it or `absolute` is set, and are
+/// separated with `/` on every platform so that output is the same wherever it is produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStyle {
+    /// whether paths are shown in full, rather than relative to `cwd`
+    pub absolute: bool,
+    /// the working directory, which is looked up once when the style is created rather than
+    /// whenever a path is shown. Without one, paths are shown as they were given.
+    pub cwd: Option<PathBuf>,
+}
+
+impl PathStyle {
+    /// Paths as they were given, only normalising their separators
+    pub const VERBATIM: Self = Self {
+        absolute: false,
+        cwd: None,
+    };
+
+    /// `path` as it is shown in diagnostics
+    pub fn show(&self, path: &Path) -> String {
+        let Some(cwd) = &self.cwd else {
+            return normalise(path);
+        };
+
+        // sources that were not read from a file, such as `repl:1`, keep their names
+        let full = cwd.join(path);
+        let path = if self.absolute && full.exists() {
+            full
+        } else {
+            full.strip_prefix(cwd)
+                .map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+        };
+        normalise(&path)
+    }
+}
+
+/// Paths relative to the current working directory
+impl Default for PathStyle {
+    fn default() -> Self {
+        Self {
+            absolute: false,
+            cwd: env::current_dir().ok(),
+        }
+    }
+}
+
+/// Separates the components of `path` with `/`, dropping any `.` components
+fn normalise(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .split('/')
+        .enumerate()
+        .filter(|(idx, part)| *part != "." && (*idx == 0 || !part.is_empty()))
+        .map(|(_, part)| part)
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,77 @@
+//! The text of each piece of source code that has been lexed, so that diagnostics can show
+//! snippets of it without reading files again
+//!
+//! Every span holds on to the source that it points into, so a source lives exactly as long as
+//! something lexed from it, such as a function defined by `eval`, and is freed along with it.
+//! Nothing is kept globally, so separate engines never see each other's sources.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A piece of source code that spans point into
+///
+/// Two are only equal if they are the same piece of source, not merely the same text.
+#[derive(Clone)]
+pub struct FileId(Arc<SourceFile>);
+
+struct SourceFile {
+    /// the path of the file, or a name such as `repl:1` if the source was not read from one
+    name: PathBuf,
+    text: Box<str>,
+}
+
+impl FileId {
+    /// Holds on to `text` under `name`
+    pub fn new(name: impl AsRef<Path>, text: &str) -> Self {
+        Self(Arc::new(SourceFile {
+            name: name.as_ref().to_path_buf(),
+            text: Box::from(text),
+        }))
+    }
+
+    pub fn name(&self) -> &Path {
+        &self.0.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+}
+
+impl PartialEq for FileId {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for FileId {}
+
+impl Hash for FileId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl fmt::Debug for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileId({:?})", self.0.name)
+    }
+}
+
+/// Only the name is serialised, so a deserialised source has no text to show snippets of
+impl Serialize for FileId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.name.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FileId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = PathBuf::deserialize(deserializer)?;
+        Ok(Self::new(name, ""))
+    }
+}
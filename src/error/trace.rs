@@ -0,0 +1,86 @@
+use color_eyre::Report;
+use colored::Colorize;
+use std::fmt;
+use thiserror::Error;
+
+use super::{diagnostics::PathStyle, Span};
+
+/// Runs of identical frames longer than this are collapsed into a single line
+const COLLAPSE_AFTER: usize = 3;
+
+/// A call that was in progress when an error occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// the name of the function that was called
+    pub name: String,
+    /// where the function was called from
+    pub call: Span,
+}
+
+/// An error that occurred inside of a function, along with the calls that led to it
+#[derive(Error, Debug)]
+#[error("{error}{}", Frames(.frames, &PathStyle::VERBATIM))]
+pub struct Traced {
+    pub error: Report,
+    /// innermost call first
+    pub frames: Vec<Frame>,
+}
+
+/// Records that `error` propagated out of the call described by `frame`
+pub fn trace(mut error: Report, frame: Frame) -> Report {
+    if let Some(traced) = error.downcast_mut::<Traced>() {
+        traced.frames.push(frame);
+        return error;
+    }
+
+    Traced {
+        error,
+        frames: vec![frame],
+    }
+    .into()
+}
+
+/// Lists the frames of a trace, collapsing long runs of identical frames, which deep recursion
+/// produces, into a single line. The paths of files are shown in the given style.
+pub struct Frames<'a>(pub &'a [Frame], pub &'a PathStyle);
+
+impl fmt::Display for Frames<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(frames, style) = self;
+        write!(f, "\n{}", "stack trace:".cyan().bold())?;
+
+        let mut idx = 0;
+        while idx < frames.len() {
+            let frame = &frames[idx];
+            let run = frames[idx..]
+                .iter()
+                .take_while(|other| *other == frame)
+                .count();
+
+            let shown = if run > COLLAPSE_AFTER { 1 } else { run };
+            for _ in 0..shown {
+                write!(
+                    f,
+                    "\n    in {}, called at {}",
+                    frame.name,
+                    frame.call.location(style)
+                )?;
+            }
+            if run > shown {
+                write!(
+                    f,
+                    "\n    … {} more frames of {} at ",
+                    run - shown,
+                    frame.name
+                )?;
+                if let Some(file) = &frame.call.file {
+                    write!(f, "{}:", style.show(file.name()))?;
+                }
+                write!(f, "{} …", frame.call.line)?;
+            }
+
+            idx += run;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,639 @@
+//! A machine readable description of atium's grammar, which documentation is generated from
+//!
+//! The expression rules are derived from the binding powers of [`TokenKind`], which the parser
+//! also uses, so that the precedence they document is always the precedence that is parsed.
+use std::fmt::{self, Write};
+
+use crate::token::TokenKind;
+
+/// The right hand side of a rule
+#[derive(Debug, Clone)]
+pub enum Production {
+    /// Source text that appears literally
+    Terminal(&'static str),
+    /// A token that is not a fixed piece of text, such as an identifier
+    Token(&'static str),
+    /// Another rule
+    Rule(String),
+    Seq(Vec<Self>),
+    Choice(Vec<Self>),
+    Optional(Box<Self>),
+    /// Zero or more repetitions
+    Repeat(Box<Self>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub production: Production,
+}
+
+/// The notation that a grammar is written out in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// ISO 14977 EBNF
+    Ebnf,
+    /// The W3C flavour of EBNF, which railroad diagram generators accept
+    Railroad,
+}
+
+/// Operators that can appear in expressions, along with their source text
+const OPERATORS: [(TokenKind, &str); 21] = [
+    (TokenKind::Equal, "="),
+    (TokenKind::Or, "or"),
+    (TokenKind::And, "and"),
+    (TokenKind::PipeGreater, "|>"),
+    (TokenKind::EqualEqual, "=="),
+    (TokenKind::BangEqual, "!="),
+    (TokenKind::Greater, ">"),
+    (TokenKind::GreaterEqual, ">="),
+    (TokenKind::Less, "<"),
+    (TokenKind::LessEqual, "<="),
+    (TokenKind::Is, "is"),
+    (TokenKind::Plus, "+"),
+    (TokenKind::Minus, "-"),
+    (TokenKind::Bar, "|"),
+    (TokenKind::Star, "*"),
+    (TokenKind::Slash, "/"),
+    (TokenKind::Ampersand, "&"),
+    (TokenKind::Bang, "!"),
+    (TokenKind::LeftParen, "("),
+    (TokenKind::Dot, "."),
+    (TokenKind::LeftBracket, "["),
+];
+
+/// The rules of the grammar, starting with the whole program
+pub fn grammar() -> Vec<Rule> {
+    use Production::*;
+
+    let mut rules = vec![
+        rule("program", Repeat(Box::new(rule_ref("declaration")))),
+        rule(
+            "declaration",
+            Choice(vec![
+                rule_ref("varDecl"),
+                rule_ref("funDecl"),
+                rule_ref("classDecl"),
+                rule_ref("traitDecl"),
+                rule_ref("statement"),
+            ]),
+        ),
+        rule(
+            "varDecl",
+            Seq(vec![
+                Terminal("var"),
+                Choice(vec![
+                    Seq(vec![
+                        Token("IDENTIFIER"),
+                        Optional(Box::new(Seq(vec![Terminal("="), rule_ref("expression")]))),
+                    ]),
+                    Seq(vec![
+                        Terminal("("),
+                        Token("IDENTIFIER"),
+                        Repeat(Box::new(Seq(vec![Terminal(","), Token("IDENTIFIER")]))),
+                        Terminal(")"),
+                        Terminal("="),
+                        rule_ref("expression"),
+                    ]),
+                ]),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "funDecl",
+            Seq(vec![
+                Repeat(Box::new(Seq(vec![Terminal("@"), rule_ref("expression")]))),
+                Terminal("fun"),
+                rule_ref("function"),
+            ]),
+        ),
+        rule(
+            "classDecl",
+            Seq(vec![
+                Terminal("class"),
+                Token("IDENTIFIER"),
+                Optional(Box::new(Seq(vec![
+                    Terminal(":"),
+                    Token("IDENTIFIER"),
+                    Repeat(Box::new(Seq(vec![Terminal(","), Token("IDENTIFIER")]))),
+                ]))),
+                Terminal("{"),
+                Repeat(Box::new(Seq(vec![
+                    Optional(Box::new(Choice(vec![
+                        Terminal("static"),
+                        Terminal("get"),
+                        Terminal("set"),
+                    ]))),
+                    rule_ref("function"),
+                ]))),
+                Terminal("}"),
+            ]),
+        ),
+        rule(
+            "traitDecl",
+            Seq(vec![
+                Terminal("trait"),
+                Token("IDENTIFIER"),
+                Terminal("{"),
+                Repeat(Box::new(Seq(vec![
+                    Optional(Box::new(Choice(vec![
+                        Terminal("static"),
+                        Terminal("get"),
+                        Terminal("set"),
+                    ]))),
+                    Token("IDENTIFIER"),
+                    Terminal("("),
+                    Optional(Box::new(Seq(vec![
+                        Token("IDENTIFIER"),
+                        Repeat(Box::new(Seq(vec![Terminal(","), Token("IDENTIFIER")]))),
+                    ]))),
+                    Terminal(")"),
+                    Terminal(";"),
+                ]))),
+                Terminal("}"),
+            ]),
+        ),
+        rule(
+            "function",
+            Seq(vec![
+                Token("IDENTIFIER"),
+                Terminal("("),
+                Optional(Box::new(rule_ref("parameters"))),
+                Terminal(")"),
+                rule_ref("block"),
+            ]),
+        ),
+        rule(
+            "parameters",
+            Choice(vec![
+                Seq(vec![
+                    rule_ref("parameter"),
+                    Repeat(Box::new(Seq(vec![Terminal(","), rule_ref("parameter")]))),
+                    Optional(Box::new(Seq(vec![
+                        Terminal(","),
+                        rule_ref("restParameter"),
+                    ]))),
+                ]),
+                rule_ref("restParameter"),
+            ]),
+        ),
+        rule(
+            "parameter",
+            Seq(vec![
+                Token("IDENTIFIER"),
+                Optional(Box::new(Seq(vec![Terminal("="), rule_ref("expression")]))),
+            ]),
+        ),
+        rule(
+            "restParameter",
+            Seq(vec![Terminal("..."), Token("IDENTIFIER")]),
+        ),
+        rule(
+            "statement",
+            Choice(vec![
+                rule_ref("printStmt"),
+                rule_ref("block"),
+                rule_ref("deferStmt"),
+                rule_ref("returnStmt"),
+                rule_ref("withStmt"),
+                rule_ref("whileStmt"),
+                rule_ref("doWhileStmt"),
+                rule_ref("forStmt"),
+                rule_ref("forInStmt"),
+                rule_ref("tryStmt"),
+                rule_ref("throwStmt"),
+                rule_ref("matchStmt"),
+                rule_ref("exprStmt"),
+            ]),
+        ),
+        rule(
+            "printStmt",
+            Seq(vec![
+                Terminal("print"),
+                rule_ref("expression"),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "block",
+            Seq(vec![
+                Terminal("{"),
+                Repeat(Box::new(rule_ref("declaration"))),
+                Terminal("}"),
+            ]),
+        ),
+        rule(
+            "deferStmt",
+            Seq(vec![
+                Terminal("defer"),
+                rule_ref("expression"),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "returnStmt",
+            Seq(vec![
+                Terminal("return"),
+                Optional(Box::new(rule_ref("expression"))),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "withStmt",
+            Seq(vec![
+                Terminal("with"),
+                Terminal("("),
+                Terminal("var"),
+                Token("IDENTIFIER"),
+                Terminal("="),
+                rule_ref("expression"),
+                Terminal(")"),
+                rule_ref("block"),
+            ]),
+        ),
+        rule(
+            "whileStmt",
+            Seq(vec![
+                Terminal("while"),
+                Terminal("("),
+                rule_ref("expression"),
+                Terminal(")"),
+                rule_ref("statement"),
+            ]),
+        ),
+        rule(
+            "doWhileStmt",
+            Seq(vec![
+                Terminal("do"),
+                rule_ref("statement"),
+                Terminal("while"),
+                Terminal("("),
+                rule_ref("expression"),
+                Terminal(")"),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "forInStmt",
+            Seq(vec![
+                Terminal("for"),
+                Token("IDENTIFIER"),
+                Terminal("in"),
+                rule_ref("expression"),
+                rule_ref("block"),
+            ]),
+        ),
+        rule(
+            "forStmt",
+            Seq(vec![
+                Terminal("for"),
+                Terminal("("),
+                Choice(vec![
+                    rule_ref("varDecl"),
+                    rule_ref("exprStmt"),
+                    Terminal(";"),
+                ]),
+                Optional(Box::new(rule_ref("expression"))),
+                Terminal(";"),
+                Optional(Box::new(rule_ref("expression"))),
+                Terminal(")"),
+                rule_ref("statement"),
+            ]),
+        ),
+        rule(
+            "tryStmt",
+            Seq(vec![
+                Terminal("try"),
+                rule_ref("block"),
+                Terminal("catch"),
+                Terminal("("),
+                Token("IDENTIFIER"),
+                Terminal(")"),
+                rule_ref("block"),
+            ]),
+        ),
+        rule(
+            "throwStmt",
+            Seq(vec![
+                Terminal("throw"),
+                rule_ref("expression"),
+                Terminal(";"),
+            ]),
+        ),
+        rule(
+            "matchStmt",
+            Seq(vec![
+                Terminal("match"),
+                rule_ref("expression"),
+                Terminal("{"),
+                Repeat(Box::new(rule_ref("arm"))),
+                Terminal("}"),
+            ]),
+        ),
+        rule(
+            "arm",
+            Seq(vec![
+                rule_ref("pattern"),
+                Terminal("=>"),
+                rule_ref("statement"),
+                Optional(Box::new(Terminal(","))),
+            ]),
+        ),
+        rule(
+            "pattern",
+            Choice(vec![
+                Seq(vec![Optional(Box::new(Terminal("-"))), Token("NUMBER")]),
+                Seq(vec![
+                    Token("STRING"),
+                    Optional(Box::new(Seq(vec![Terminal("..."), Token("IDENTIFIER")]))),
+                ]),
+                Seq(vec![Terminal("..."), Token("IDENTIFIER"), Token("STRING")]),
+                Seq(vec![Terminal("regex"), Token("STRING")]),
+                Terminal("true"),
+                Terminal("false"),
+                Terminal("nil"),
+                Terminal("_"),
+            ]),
+        ),
+        rule("exprStmt", Seq(vec![rule_ref("expression"), Terminal(";")])),
+    ];
+
+    rules.extend(expression_rules());
+    rules.extend([
+        rule(
+            "arguments",
+            Choice(vec![
+                Seq(vec![
+                    rule_ref("expression"),
+                    Repeat(Box::new(Seq(vec![Terminal(","), rule_ref("expression")]))),
+                    Repeat(Box::new(Seq(vec![
+                        Terminal(","),
+                        rule_ref("namedArgument"),
+                    ]))),
+                ]),
+                Seq(vec![
+                    rule_ref("namedArgument"),
+                    Repeat(Box::new(Seq(vec![
+                        Terminal(","),
+                        rule_ref("namedArgument"),
+                    ]))),
+                ]),
+            ]),
+        ),
+        rule(
+            "namedArgument",
+            Seq(vec![
+                Token("IDENTIFIER"),
+                Terminal(":"),
+                rule_ref("expression"),
+            ]),
+        ),
+        rule(
+            "primary",
+            Choice(vec![
+                Token("NUMBER"),
+                Token("STRING"),
+                Terminal("true"),
+                Terminal("false"),
+                Terminal("nil"),
+                Terminal("this"),
+                Seq(vec![
+                    Terminal("set{"),
+                    Optional(Box::new(Seq(vec![
+                        rule_ref("expression"),
+                        Repeat(Box::new(Seq(vec![Terminal(","), rule_ref("expression")]))),
+                    ]))),
+                    Terminal("}"),
+                ]),
+                Token("IDENTIFIER"),
+                Seq(vec![
+                    Terminal("("),
+                    rule_ref("expression"),
+                    Repeat(Box::new(Seq(vec![Terminal(","), rule_ref("expression")]))),
+                    Terminal(")"),
+                ]),
+                Seq(vec![
+                    Terminal("quote"),
+                    Terminal("{"),
+                    Choice(vec![
+                        rule_ref("expression"),
+                        Repeat(Box::new(rule_ref("declaration"))),
+                    ]),
+                    Terminal("}"),
+                ]),
+                Seq(vec![
+                    Terminal("unquote"),
+                    Terminal("("),
+                    rule_ref("expression"),
+                    Terminal(")"),
+                ]),
+            ]),
+        ),
+    ]);
+
+    rules
+}
+
+/// Derives a rule for each level of precedence in the infix binding power table, loosest first,
+/// followed by the prefix and postfix operators
+fn expression_rules() -> Vec<Rule> {
+    use Production::*;
+
+    let mut levels: Vec<(u8, u8, Vec<&str>, String)> = vec![];
+    for (kind, symbol) in OPERATORS {
+        let Some((l_bp, r_bp)) = kind.infix_bp() else {
+            continue;
+        };
+        match levels.iter_mut().find(|(bp, ..)| *bp == l_bp) {
+            Some((.., symbols, _)) => symbols.push(symbol),
+            None => levels.push((l_bp, r_bp, vec![symbol], level_name(&kind))),
+        }
+    }
+    levels.sort_by_key(|(l_bp, ..)| *l_bp);
+
+    let prefix = OPERATORS
+        .iter()
+        .filter(|(kind, _)| kind.prefix_bp().is_some())
+        .map(|(_, symbol)| Terminal(symbol))
+        .collect::<Vec<_>>();
+    let prefix_r_bp = TokenKind::Minus.prefix_bp().unwrap();
+    // the operand of a prefix operator extends over every infix operator binding at least as
+    // tightly as it does
+    let prefix_operand = levels
+        .iter()
+        .find(|(l_bp, ..)| *l_bp >= prefix_r_bp)
+        .map_or_else(|| String::from("unary"), |(.., name)| name.clone());
+
+    let mut rules = vec![rule(
+        "expression",
+        levels
+            .first()
+            .map_or_else(|| rule_ref("unary"), |(.., name)| rule_ref(name)),
+    )];
+    for (idx, (l_bp, r_bp, symbols, name)) in levels.iter().enumerate() {
+        let operand = levels
+            .get(idx + 1)
+            .map_or_else(|| rule_ref("unary"), |(.., next)| rule_ref(next));
+        let op = match symbols[..] {
+            [symbol] => Terminal(symbol),
+            _ => Choice(symbols.iter().map(|symbol| Terminal(symbol)).collect()),
+        };
+
+        let production = if l_bp < r_bp {
+            // left associative
+            Seq(vec![
+                operand.clone(),
+                Repeat(Box::new(Seq(vec![op, operand]))),
+            ])
+        } else {
+            // right associative
+            Seq(vec![
+                operand,
+                Optional(Box::new(Seq(vec![op, rule_ref(name)]))),
+            ])
+        };
+        rules.push(rule(name, production));
+    }
+
+    rules.push(rule(
+        "unary",
+        Choice(vec![
+            Seq(vec![Choice(prefix), rule_ref(&prefix_operand)]),
+            rule_ref("postfix"),
+        ]),
+    ));
+
+    let postfix = OPERATORS
+        .iter()
+        .filter(|(kind, _)| kind.postfix_bp().is_some())
+        .map(|(kind, _)| match kind {
+            TokenKind::LeftParen => Seq(vec![
+                Terminal("("),
+                Optional(Box::new(rule_ref("arguments"))),
+                Terminal(")"),
+            ]),
+            TokenKind::Dot => Seq(vec![Terminal("."), Token("IDENTIFIER")]),
+            TokenKind::LeftBracket => {
+                Seq(vec![Terminal("["), rule_ref("expression"), Terminal("]")])
+            }
+            x => unreachable!("{x:?} has no postfix syntax"),
+        })
+        .collect();
+    rules.push(rule(
+        "postfix",
+        Seq(vec![rule_ref("primary"), Repeat(Box::new(Choice(postfix)))]),
+    ));
+
+    rules
+}
+
+/// The name of the rule for the level of precedence that `op` belongs to
+fn level_name(op: &TokenKind) -> String {
+    let name = match op {
+        TokenKind::Equal => "assignment",
+        TokenKind::Or => "logicOr",
+        TokenKind::And => "logicAnd",
+        TokenKind::PipeGreater => "pipeline",
+        TokenKind::EqualEqual | TokenKind::BangEqual => "equality",
+        TokenKind::Greater
+        | TokenKind::GreaterEqual
+        | TokenKind::Less
+        | TokenKind::LessEqual
+        | TokenKind::Is => "comparison",
+        TokenKind::Plus | TokenKind::Minus | TokenKind::Bar => "term",
+        TokenKind::Star | TokenKind::Slash | TokenKind::Ampersand => "factor",
+        op => return format!("{op:?}").to_lowercase(),
+    };
+    name.to_string()
+}
+
+fn rule(name: &str, production: Production) -> Rule {
+    Rule {
+        name: name.to_string(),
+        production,
+    }
+}
+
+fn rule_ref(name: &str) -> Production {
+    Production::Rule(name.to_string())
+}
+
+/// Writes out `rules` in `notation`
+pub fn render(rules: &[Rule], notation: Notation) -> String {
+    let mut out = String::new();
+    for rule in rules {
+        let production = Notated(&rule.production, notation);
+        match notation {
+            Notation::Ebnf => writeln!(out, "{} = {production} ;", rule.name),
+            Notation::Railroad => writeln!(out, "{} ::= {production}", rule.name),
+        }
+        .expect("writing to a string cannot fail");
+    }
+    out
+}
+
+/// A production written out in a particular notation
+struct Notated<'a>(&'a Production, Notation);
+
+impl fmt::Display for Notated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(production, notation) = *self;
+        let separator = match notation {
+            Notation::Ebnf => " , ",
+            Notation::Railroad => " ",
+        };
+
+        match production {
+            Production::Terminal(text) if text.contains('"') => write!(f, "'{text}'"),
+            Production::Terminal(text) => write!(f, "\"{text}\""),
+            Production::Token(name) => write!(f, "{name}"),
+            Production::Rule(name) => write!(f, "{name}"),
+            Production::Seq(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{}", Grouped(item, notation))?;
+                }
+                Ok(())
+            }
+            Production::Choice(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", Notated(item, notation))?;
+                }
+                Ok(())
+            }
+            Production::Optional(inner) => match notation {
+                Notation::Ebnf => write!(f, "[ {} ]", Notated(inner, notation)),
+                Notation::Railroad => write!(f, "{}?", Grouped(inner, notation)),
+            },
+            Production::Repeat(inner) => match notation {
+                Notation::Ebnf => write!(f, "{{ {} }}", Notated(inner, notation)),
+                Notation::Railroad => write!(f, "{}*", Grouped(inner, notation)),
+            },
+        }
+    }
+}
+
+/// A production that is parenthesised if it would otherwise bind incorrectly to its surroundings
+struct Grouped<'a>(&'a Production, Notation);
+
+impl fmt::Display for Grouped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(production, notation) = *self;
+        let needs_parens = match production {
+            Production::Choice(_) => true,
+            Production::Seq(_) => notation == Notation::Railroad,
+            _ => false,
+        };
+
+        if needs_parens {
+            write!(f, "( {} )", Notated(production, notation))
+        } else {
+            write!(f, "{}", Notated(production, notation))
+        }
+    }
+}
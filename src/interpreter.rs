@@ -1,36 +1,125 @@
 use color_eyre::{Report, Result};
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use crate::{
-    ast::{Expr, Stmt},
-    dump,
+    ast::{Expr, FunDecl, MethodKind, Stmt},
+    atium::Options,
+    diagnostic, dump,
     environment::Env,
-    error::RuntimeError,
+    error::{
+        trace::{trace, Frame},
+        RuntimeError,
+    },
     token::{Token, TokenKind, Type, Value},
 };
 
-pub(super) struct Interpreter {
+pub(crate) use self::native::ident;
+pub use self::{
+    class::{Class, Instance, Trait},
+    function::Function,
+    native::{define_natives, NativeFn},
+};
+
+mod catch;
+mod class;
+mod function;
+mod iterate;
+mod native;
+mod ops;
+mod pattern;
+mod quote;
+
+/// How control leaves a statement
+pub(super) enum Flow {
+    /// execution continues with the following statement
+    Normal,
+    /// a `return` is unwinding to the call of the enclosing function
+    Return(Value),
+    /// a `return` of a call in tail position is unwinding, for the call of the enclosing function
+    /// to make in its place
+    TailCall(Box<Call>),
+}
+
+/// A call to a function declared in atium, whose arguments have been evaluated
+pub(super) struct Call {
+    fun: Function,
+    /// the token that closes the argument list
+    paren: Token,
+    /// the argument for each parameter, if one was given
+    args: Vec<Option<Value>>,
+    /// the arguments left over for the rest parameter
+    rest: Vec<Value>,
+}
+
+pub struct Interpreter {
     stmts: Vec<Stmt>,
-    env: RefCell<Env>,
+    /// the innermost scope that is currently being executed in
+    env: RefCell<Rc<RefCell<Env>>>,
+    /// expressions deferred by each block that is currently executing, innermost last
+    deferred: RefCell<Vec<Vec<Expr>>>,
+    /// the number of `try` statements currently executing. While one is, blocks are left at
+    /// their first error instead of running on to report every error they contain
+    catching: Cell<usize>,
+    /// values raised by `throw`, see [`RuntimeError::Thrown`]
+    thrown: RefCell<Vec<Value>>,
+    /// the number of `try` and `with` bodies, within the function currently executing, that
+    /// enclose the statement being executed
+    guarded: Cell<usize>,
+    /// the index in `deferred` of the outermost block of the function currently executing
+    frame: Cell<usize>,
+    /// the number of calls to functions declared in atium that are in progress
+    depth: Cell<usize>,
+    /// the regular expressions of the `regex` patterns matched so far, keyed by their source
+    #[cfg(feature = "regex")]
+    regexes: RefCell<std::collections::HashMap<String, regex::Regex>>,
+    options: Options,
 }
 
 impl Interpreter {
     pub fn new(stmts: Vec<Stmt>) -> Self {
+        Self::with_env(stmts, Rc::new(RefCell::new(Env::global())))
+    }
+
+    /// Creates an interpreter whose global scope is `env`, allowing state to be shared between
+    /// several programs
+    pub fn with_env(stmts: Vec<Stmt>, env: Rc<RefCell<Env>>) -> Self {
         Self {
             stmts,
-            env: RefCell::new(Env::new()),
+            env: RefCell::new(env),
+            deferred: RefCell::new(vec![]),
+            catching: Cell::new(0),
+            thrown: RefCell::new(vec![]),
+            guarded: Cell::new(0),
+            frame: Cell::new(0),
+            depth: Cell::new(0),
+            #[cfg(feature = "regex")]
+            regexes: RefCell::default(),
+            options: Options::default(),
         }
     }
 
+    #[must_use]
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn interpret(self) -> Result<(), Vec<Report>> {
-        let errors = self
+        self.deferred.borrow_mut().push(vec![]);
+        let mut errors = self
             .stmts
             .iter()
-            .map(|stmt| self.execute(stmt).err())
-            .flatten() // only statements that produces errors
+            // only statements that produces errors
+            .filter_map(|stmt| match stmt {
+                Stmt::Expr(expr) if self.options.echo => self.echo(expr).err().map(|err| vec![err]),
+                stmt => self.execute(stmt).err(),
+            })
             .flatten() // flatten all errors into one stream
-            .flatten() // gets only errors, ignoring successes
             .collect::<Vec<Report>>();
+        errors.extend(self.run_deferred());
 
         if errors.is_empty() {
             Ok(())
@@ -39,50 +128,313 @@ impl Interpreter {
         }
     }
 
-    fn execute(&self, stmt: &Stmt) -> Result<(), Vec<Option<Report>>> {
-        let errors = match stmt {
-            Stmt::Expr(expr) => vec![self.expression(expr).err()],
-            Stmt::Block(stmts) => self
-                .execute_block(stmts, Env::new())
-                .err()
-                .map_or(vec![], |v| v),
-            Stmt::Print(expr) => vec![self.print(expr).err()],
-            Stmt::Var { name, value } => vec![self.def_var(name.clone(), value.clone()).err()],
+    /// Executes the program like [`Interpreter::interpret`], but produces the value of its final
+    /// statement if it is an expression statement, and `nil` otherwise
+    pub fn evaluate(self) -> Result<Value, Vec<Report>> {
+        self.deferred.borrow_mut().push(vec![]);
+        let value = self.run(&self.stmts);
+        let mut errors = self.run_deferred();
+
+        match value {
+            Ok(value) if errors.is_empty() => Ok(value),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.insert(0, err);
+                Err(errors)
+            }
+        }
+    }
+
+    /// Executes `stmts` like [`Interpreter::run`], but with a frame of its own for the
+    /// expressions that they defer, which are evaluated as soon as they finish
+    pub(crate) fn run_frame(&self, stmts: &[Stmt]) -> Result<Value> {
+        self.deferred.borrow_mut().push(vec![]);
+        let value = self.run(stmts);
+        let errors = self.run_deferred();
+
+        match (value, errors.into_iter().next()) {
+            (Err(err), _) | (Ok(_), Some(err)) => Err(err),
+            (Ok(value), None) => Ok(value),
+        }
+    }
+
+    /// Executes `stmts` in the current scope, producing the value of the trailing expression
+    /// statement
+    fn run(&self, stmts: &[Stmt]) -> Result<Value> {
+        let (last, rest) = match stmts.split_last() {
+            Some((Stmt::Expr(expr), rest)) => (Some(expr), rest),
+            _ => (None, stmts),
         };
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
+        for stmt in rest {
+            match self.execute(stmt).map_err(first_error)? {
+                Flow::Normal => (),
+                Flow::Return(value) => return Ok(value),
+                Flow::TailCall(call) => return self.call_function(*call),
+            }
         }
+
+        last.map_or(Ok(Value::Null), |expr| self.expression(expr))
     }
 
-    fn execute_block(&self, stmts: &[Stmt], new_env: Env) -> Result<(), Vec<Option<Report>>> {
-        let prev_env = self.env.replace(new_env);
-        self.env.borrow_mut().set_parent(prev_env.clone());
+    fn execute(&self, stmt: &Stmt) -> Result<Flow, Vec<Report>> {
+        let flow = match stmt {
+            Stmt::Expr(expr) => self.expression(expr).map(|_| Flow::Normal),
+            Stmt::Block(stmts) => {
+                return self.execute_block(stmts, Env::with_parent(self.scope()));
+            }
+            Stmt::Print(expr) => self.print(expr).map(|()| Flow::Normal),
+            Stmt::Var { name, value } => self
+                .def_var(name.clone(), value.clone())
+                .map(|()| Flow::Normal),
+            Stmt::VarTuple { names, value } => self.def_vars(names, value).map(|()| Flow::Normal),
+            Stmt::Function { decorators, decl } => {
+                self.def_fun(decorators, decl).map(|()| Flow::Normal)
+            }
+            Stmt::Class {
+                name,
+                doc,
+                methods,
+                statics,
+                getters,
+                setters,
+                traits,
+            } => self
+                .def_class(
+                    name,
+                    doc.as_ref(),
+                    [methods, statics, getters, setters],
+                    traits,
+                )
+                .map(|()| Flow::Normal),
+            Stmt::Trait { name, doc, methods } => {
+                let r#trait = Trait {
+                    name: name.clone(),
+                    doc: doc.clone(),
+                    methods: methods.clone(),
+                };
+                self.scope()
+                    .borrow_mut()
+                    .define(name.clone(), Some(Value::Trait(Rc::new(r#trait))));
+                Ok(Flow::Normal)
+            }
+            Stmt::Return {
+                value: Some(Expr::Call(callee, paren, args, named)),
+                ..
+            } => self.tail_call(callee, paren, args, named),
+            Stmt::Return { value, .. } => value
+                .as_ref()
+                .map_or(Ok(Value::Null), |value| self.expression(value))
+                .map(Flow::Return),
+            Stmt::Defer(expr) => {
+                if let Some(frame) = self.deferred.borrow_mut().last_mut() {
+                    frame.push(expr.clone());
+                }
+                Ok(Flow::Normal)
+            }
+            Stmt::With {
+                keyword,
+                name,
+                value,
+                body,
+            } => return self.with(keyword, name, value, body),
+            Stmt::While {
+                keyword,
+                condition,
+                body,
+            } => return self.while_loop(keyword, condition, body),
+            Stmt::ForIn {
+                keyword,
+                name,
+                iterable,
+                body,
+            } => return self.for_in(keyword, name, iterable, body),
+            Stmt::DoWhile {
+                body,
+                keyword,
+                condition,
+            } => return self.do_while(body, keyword, condition),
+            Stmt::Match { value, arms, .. } => return self.match_arms(value, arms),
+            Stmt::Throw { keyword, value } => self.throw(keyword, value),
+            Stmt::Try {
+                body,
+                name,
+                handler,
+            } => return self.try_catch(body, name, handler),
+        };
 
-        let errors = stmts
-            .iter()
-            .map(|stmt| self.execute(stmt).err())
-            .flatten() // only statements that produce errors
-            .flatten() // Item: Vec<Option<Report>> -> Option<Report>
-            .collect::<Vec<Option<Report>>>();
+        flow.map_err(|err| vec![err])
+    }
+
+    /// Executes `stmts` in `new_env`, stopping early if a `return` is encountered
+    ///
+    /// However the block is left, the expressions it deferred are evaluated before its scope is
+    /// discarded.
+    fn execute_block(&self, stmts: &[Stmt], new_env: Env) -> Result<Flow, Vec<Report>> {
+        let prev_env = self.env.replace(Rc::new(RefCell::new(new_env)));
+        self.deferred.borrow_mut().push(vec![]);
+
+        let mut flow = Flow::Normal;
+        let mut errors = vec![];
+        for stmt in stmts {
+            match self.execute(stmt) {
+                Ok(Flow::Normal) => (),
+                Ok(unwind) => {
+                    flow = unwind;
+                    break;
+                }
+                Err(errs) => {
+                    errors.extend(errs);
+                    if self.catching.get() > 0 {
+                        break;
+                    }
+                }
+            }
+        }
 
+        errors.extend(self.run_deferred());
         self.env.replace(prev_env);
         if errors.is_empty() {
-            Ok(())
+            Ok(flow)
         } else {
             Err(errors)
         }
     }
 
+    /// Executes `body` with the resource produced by `value` bound to `name`, disposing of the
+    /// resource however the body is left
+    fn with(
+        &self,
+        keyword: &Token,
+        name: &Token,
+        value: &Expr,
+        body: &[Stmt],
+    ) -> Result<Flow, Vec<Report>> {
+        let resource = self.expression(value).map_err(|err| vec![err])?;
+
+        let mut env = Env::with_parent(self.scope());
+        env.define(name.clone(), Some(resource.clone()));
+        self.guarded.set(self.guarded.get() + 1);
+        let flow = self.execute_block(body, env);
+        self.guarded.set(self.guarded.get() - 1);
+
+        match (flow, self.dispose(resource, keyword)) {
+            (Ok(flow), Ok(())) => Ok(flow),
+            (Ok(_), Err(err)) => Err(vec![err]),
+            (Err(errs), Ok(())) => Err(errs),
+            (Err(mut errs), Err(err)) => {
+                errs.push(err);
+                Err(errs)
+            }
+        }
+    }
+
+    /// Executes `body` for as long as `condition` is true, stopping at the first error
+    fn while_loop(
+        &self,
+        keyword: &Token,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Result<Flow, Vec<Report>> {
+        while self
+            .condition(keyword, condition)
+            .map_err(|err| vec![err])?
+        {
+            match self.execute(body)? {
+                Flow::Normal => (),
+                unwind => return Ok(unwind),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Executes `body`, then keeps executing it for as long as `condition` is true
+    fn do_while(
+        &self,
+        body: &Stmt,
+        keyword: &Token,
+        condition: &Expr,
+    ) -> Result<Flow, Vec<Report>> {
+        loop {
+            match self.execute(body)? {
+                Flow::Normal => (),
+                unwind => return Ok(unwind),
+            }
+            if !self
+                .condition(keyword, condition)
+                .map_err(|err| vec![err])?
+            {
+                return Ok(Flow::Normal);
+            }
+        }
+    }
+
+    /// Evaluates the condition of the statement introduced by `keyword`, which must be a boolean
+    fn condition(&self, keyword: &Token, condition: &Expr) -> Result<bool> {
+        match self.expression(condition)? {
+            Value::Boolean(condition) => Ok(condition),
+            value => dump!(
+                RuntimeError::InvalidType::<&str>(value.into(), vec![Type::Boolean]),
+                condition
+                    .first_token()
+                    .map_or_else(|| keyword.span.clone(), |tok| tok.span.clone())
+            ),
+        }
+    }
+
+    /// Disposes of a resource by calling its `close` method, or failing that its `dispose` method
+    ///
+    /// `Null` is accepted and ignored so that a resource which failed to be acquired does not need
+    /// to be special cased.
+    fn dispose(&self, resource: Value, keyword: &Token) -> Result<()> {
+        match resource {
+            Value::Null => Ok(()),
+            _ => match Self::disposer(&resource) {
+                Some(method) => self.call(method, keyword, vec![]).map(|_| ()),
+                None => dump!(
+                    RuntimeError::NotDisposable::<&str>(resource.into()),
+                    keyword.span.clone()
+                ),
+            },
+        }
+    }
+
+    /// Looks up the method that disposes of `resource`, `close` or failing that `dispose`
+    fn disposer(resource: &Value) -> Option<Value> {
+        let Value::Instance(instance) = resource else {
+            return None;
+        };
+        instance
+            .method("close")
+            .or_else(|| instance.method("dispose"))
+            .map(Value::Function)
+    }
+
+    /// Evaluates the expressions deferred by the innermost block, most recently deferred first
+    fn run_deferred(&self) -> Vec<Report> {
+        let frame = self.deferred.borrow_mut().pop().unwrap_or_default();
+        frame
+            .iter()
+            .rev()
+            .filter_map(|expr| self.expression(expr).err())
+            .collect()
+    }
+
+    /// The innermost scope that is currently being executed in
+    fn scope(&self) -> Rc<RefCell<Env>> {
+        Rc::clone(&self.env.borrow())
+    }
+
     fn get_var(&self, ident: &Token) -> Result<Value> {
-        match self.env.borrow_mut().get(ident) {
+        match self.scope().borrow().get(ident) {
             Some(val) => match val {
-                Some(val) => Ok(val.clone()),
-                None => dump!(RuntimeError::UninitialisedVar(ident.lex())),
+                Some(val) => Ok(val),
+                None => dump!(
+                    RuntimeError::UninitialisedVar(ident.lex()),
+                    ident.span.clone()
+                ),
             },
-            None => dump!(RuntimeError::InvalidIdent(ident.lex())),
+            None => dump!(RuntimeError::InvalidIdent(ident.lex()), ident.span.clone()),
         }
     }
 
@@ -90,17 +442,131 @@ impl Interpreter {
         if let Some(expr) = value {
             match self.expression(&expr) {
                 Ok(val) => {
-                    self.env.borrow_mut().define(ident, Some(val));
-                    return Ok(());
+                    self.scope().borrow_mut().define(ident, Some(val));
+                    Ok(())
                 }
                 Err(err) => Err(err),
             }
         } else {
-            self.env.borrow_mut().define(ident, None);
+            self.scope().borrow_mut().define(ident, None);
             Ok(())
         }
     }
 
+    /// Declares a variable for each element of `value`, the elements being matched to `names` by
+    /// position
+    fn def_vars(&self, names: &[Token], value: &Expr) -> Result<()> {
+        let values = unpack(self.expression(value)?, names)?;
+        let scope = self.scope();
+        for (name, value) in names.iter().zip(values) {
+            scope.borrow_mut().define(name.clone(), Some(value));
+        }
+        Ok(())
+    }
+
+    /// Binds a function to its name, after passing it through each of its decorators
+    fn def_fun(&self, decorators: &[Expr], decl: &Rc<FunDecl>) -> Result<()> {
+        let decorators = decorators
+            .iter()
+            .map(|decorator| self.expression(decorator))
+            .collect::<Result<Vec<Value>>>()?;
+
+        let mut fun = Value::Function(Function {
+            decl: Rc::clone(decl),
+            closure: self.scope(),
+        });
+        for decorator in decorators.into_iter().rev() {
+            fun = self.call(decorator, &decl.name, vec![fun])?;
+        }
+
+        self.scope()
+            .borrow_mut()
+            .define(decl.name.clone(), Some(fun));
+        Ok(())
+    }
+
+    /// Defines the class `name`, given its methods, static methods, getters and setters
+    ///
+    /// Errors if the class is missing a method required by one of the traits it implements, which
+    /// the parser could not check if the trait was declared elsewhere.
+    fn def_class(
+        &self,
+        name: &Token,
+        doc: Option<&String>,
+        methods: [&Vec<Rc<FunDecl>>; 4],
+        traits: &[Token],
+    ) -> Result<()> {
+        let [methods, statics, getters, setters] = methods;
+        let functions = |decls: &[Rc<FunDecl>]| {
+            decls
+                .iter()
+                .map(|decl| {
+                    let method = Function {
+                        decl: Rc::clone(decl),
+                        closure: self.scope(),
+                    };
+                    (decl.name.lex(), method)
+                })
+                .collect()
+        };
+        let class = Class {
+            name: name.clone(),
+            doc: doc.cloned(),
+            methods: functions(methods),
+            statics: functions(statics),
+            getters: functions(getters),
+            setters: functions(setters),
+            traits: traits
+                .iter()
+                .map(|r#trait| self.r#trait(r#trait))
+                .collect::<Result<_>>()?,
+        };
+
+        for (r#trait, token) in class.traits.iter().zip(traits) {
+            let missing = r#trait.methods.iter().find(|method| {
+                let decls = match method.kind {
+                    MethodKind::Method => &class.methods,
+                    MethodKind::Static => &class.statics,
+                    MethodKind::Getter => &class.getters,
+                    MethodKind::Setter => &class.setters,
+                };
+                let decl = decls.get(&method.name.lex()).map(|fun| fun.decl.as_ref());
+                !method.is_met_by(decl)
+            });
+            if let Some(method) = missing {
+                let error = diagnostic!(
+                    RuntimeError::MissingTraitMethod {
+                        class: name.lex(),
+                        r#trait: r#trait.name(),
+                        method: method.to_string(),
+                    },
+                    token.span.clone()
+                )
+                .note(
+                    format!("{} requires {method}", r#trait.name()),
+                    method.name.span.clone(),
+                );
+                return Err(error.into());
+            }
+        }
+
+        self.scope()
+            .borrow_mut()
+            .define(name.clone(), Some(Value::Class(Rc::new(class))));
+        Ok(())
+    }
+
+    /// Looks up the trait called `name`
+    fn r#trait(&self, name: &Token) -> Result<Rc<Trait>> {
+        match self.get_var(name)? {
+            Value::Trait(r#trait) => Ok(r#trait),
+            value => dump!(
+                RuntimeError::NotATrait::<&str>(value.into()),
+                name.span.clone()
+            ),
+        }
+    }
+
     /// Interpret and expression, either producing a value or an error than occurred during the
     /// interpretation of the expression.
     fn expression(&self, expr: &Expr) -> Result<Value> {
@@ -108,80 +574,605 @@ impl Interpreter {
             Expr::Literal(lit) => Ok(lit.literal.clone().unwrap()),
             Expr::Grouping(expr) => self.expression(expr),
             Expr::Variable(ident) => self.get_var(ident),
-            Expr::Assignment(ident, val) => self
-                .env
-                .borrow_mut()
-                .assign(ident.clone(), self.expression(val)?),
+            Expr::This(keyword) => self.get_var(keyword),
+            Expr::SetLiteral(_, elements) => self.set_literal(elements),
+            Expr::Tuple(_, elements) => self.tuple(elements),
+            Expr::Destructure(names, value) => self.destructure(names, value),
+            Expr::Assignment(ident, val) => {
+                let val = self.expression(val)?;
+                self.scope().borrow_mut().assign(ident.clone(), val)
+            }
+            Expr::Call(callee, paren, args, named) => {
+                let callee = self.expression(callee)?;
+                self.call_expr(callee, paren, args, named)
+            }
+            Expr::Get(object, name) => {
+                let object = self.expression(object)?;
+                self.property(object, name)
+            }
+            Expr::Set(object, name, value) => self.set_property(object, name, value),
+            Expr::Index(indexed, bracket, index) => {
+                let indexed = self.expression(indexed)?;
+                let index = self.expression(index)?;
+                Self::index(indexed, bracket, index)
+            }
+            Expr::Quote(quoted) => self.quote(quoted),
+            Expr::Unquote(keyword, _) => Self::unquote(keyword),
             Expr::Unary(op, expr) => {
                 let expr = self.expression(expr)?;
-
-                match op.kind {
-                    TokenKind::Minus => match expr {
-                        Value::Integer(a) => Ok(Value::Integer(-a)),
-                        _ => dump!(RuntimeError::InvalidType::<&str>(
-                            expr.into(),
-                            vec![Type::Integer]
-                        )),
-                    },
-                    TokenKind::Bang => match expr {
-                        Value::Boolean(a) => Ok(Value::Boolean(!a)),
-                        _ => dump!(RuntimeError::InvalidType::<&str>(
-                            expr.into(),
-                            vec![Type::Boolean]
-                        )),
-                    },
-                    _ => dump!(RuntimeError::InvalidOperator(op.lex(), vec!['-', '!'])),
+                ops::unary(op, expr)
+            }
+            Expr::Logical(left, op, right) => {
+                let left = ops::truth(op, self.expression(left)?)?;
+                // `true or _` and `false and _` are decided by their left operand alone
+                if left == (op.kind == TokenKind::Or) {
+                    return Ok(Value::Boolean(left));
                 }
+                ops::truth(op, self.expression(right)?).map(Value::Boolean)
             }
             Expr::Binary(left, op, right) => {
-                let left = self.expression(left)?;
-                let right = self.expression(right)?;
-
-                match op.kind {
-                    TokenKind::Slash => match (&left, &right) {
-                        (Value::Integer(a), Value::Integer(b)) => Ok((a / b).into()),
-                        _ => dump!(RuntimeError::InvalidTypes(
-                            op.lex(),
-                            vec![left.into(), right.into()],
-                            vec![(Type::Integer, Type::Integer)],
-                        )),
-                    },
-                    TokenKind::Minus => match (&left, &right) {
-                        (Value::Integer(a), Value::Integer(b)) => Ok((a - b).into()),
-                        _ => dump!(RuntimeError::InvalidTypes(
-                            op.lex(),
-                            vec![left.into(), right.into()],
-                            vec![(Type::Integer, Type::Integer)],
-                        )),
+                let left_value = self.expression(left)?;
+                let right_value = self.expression(right)?;
+                if op.kind != TokenKind::Plus {
+                    return ops::binary(op, left_value, right_value);
+                }
+
+                match ops::coerce(&left_value, &right_value) {
+                    Some(coerced) if self.options.coerce_strings => Ok(coerced),
+                    Some(_) => {
+                        // the operand that is not a string is the one needing conversion
+                        let (operand, value) = if matches!(left_value, Value::String(_)) {
+                            (right, &right_value)
+                        } else {
+                            (left, &left_value)
+                        };
+                        let note = format!(
+                            "convert the {} to a String with str() first, or run with \
+                             --coerce-strings to do so implicitly",
+                            Type::from(value.clone())
+                        );
+                        let span = operand
+                            .first_token()
+                            .map_or_else(|| op.span.clone(), |tok| tok.span.clone());
+
+                        Err(ops::invalid_types(op, left_value, right_value)
+                            .note(note, span)
+                            .into())
+                    }
+                    None => ops::binary(op, left_value, right_value),
+                }
+            }
+        }
+    }
+
+    /// Evaluates the elements of a set literal, in order
+    fn set_literal(&self, elements: &[Expr]) -> Result<Value> {
+        let elements = elements
+            .iter()
+            .map(|element| self.expression(element))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Value::Set(elements.into_iter().collect()))
+    }
+
+    /// Evaluates the elements of a tuple, in order
+    fn tuple(&self, elements: &[Expr]) -> Result<Value> {
+        elements
+            .iter()
+            .map(|element| self.expression(element))
+            .collect::<Result<_>>()
+            .map(Value::Tuple)
+    }
+
+    /// Assigns each element of `value` to the variable in the same position of `names`,
+    /// evaluating to `value`
+    fn destructure(&self, names: &[Token], value: &Expr) -> Result<Value> {
+        let value = self.expression(value)?;
+        for (name, element) in names.iter().zip(unpack(value.clone(), names)?) {
+            self.scope().borrow_mut().assign(name.clone(), element)?;
+        }
+        Ok(value)
+    }
+
+    /// Looks up the property `name` of `object`
+    fn property(&self, object: Value, name: &Token) -> Result<Value> {
+        if let Value::Instance(instance) = &object {
+            if let Some(getter) = instance.getter(&name.lex()) {
+                return self.call(Value::Function(getter), name, vec![]);
+            }
+        }
+
+        let property = match &object {
+            Value::String(string) => native::string::method(string, &name.lex())
+                .map(|native| Value::NativeFn(Rc::new(native))),
+            Value::Set(set) => {
+                native::set::method(set, &name.lex()).map(|native| Value::NativeFn(Rc::new(native)))
+            }
+            Value::Instance(instance) => instance.get(&name.lex()),
+            Value::Class(class) => class.statics.get(&name.lex()).cloned().map(Value::Function),
+            _ => None,
+        };
+
+        match property {
+            Some(property) => Ok(property),
+            None => dump!(
+                RuntimeError::UnknownProperty(object.into(), name.lex()),
+                name.span.clone()
+            ),
+        }
+    }
+
+    /// Assigns `value` to the property `name` of `object`, through its setter if it has one
+    fn set_property(&self, object: &Expr, name: &Token, value: &Expr) -> Result<Value> {
+        let instance = match self.expression(object)? {
+            Value::Instance(instance) => instance,
+            object => dump!(
+                RuntimeError::NotSettable::<&str>(object.into()),
+                name.span.clone()
+            ),
+        };
+        let value = self.expression(value)?;
+        match instance.setter(&name.lex()) {
+            Some(setter) => {
+                self.call(Value::Function(setter), name, vec![value.clone()])?;
+            }
+            None => {
+                instance
+                    .fields
+                    .borrow_mut()
+                    .insert(name.lex(), value.clone());
+            }
+        }
+        Ok(value)
+    }
+
+    /// Retrieves the element at `index` of `indexed`, which for strings is a grapheme and for maps
+    /// is the value at the key `index`
+    fn index(indexed: Value, bracket: &Token, index: Value) -> Result<Value> {
+        if let Value::Map(map) = &indexed {
+            match map.get(&index) {
+                Some(value) => return Ok(value.clone()),
+                None => dump!(
+                    RuntimeError::MissingKey::<&str>(index.nested()),
+                    bracket.span.clone()
+                ),
+            }
+        }
+
+        let Value::Integer(index) = index else {
+            dump!(
+                RuntimeError::InvalidType::<&str>(index.into(), vec![Type::Integer]),
+                bracket.span.clone()
+            )
+        };
+
+        let (elem, len) = match &indexed {
+            Value::String(string) => (
+                native::string::grapheme(string, index),
+                native::string::len_of(string),
+            ),
+            Value::List(list) | Value::Tuple(list) => (
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| list.get(index).cloned()),
+                list.len(),
+            ),
+            _ => dump!(
+                RuntimeError::NotIndexable::<&str>(indexed.into()),
+                bracket.span.clone()
+            ),
+        };
+
+        match elem {
+            Some(elem) => Ok(elem),
+            None => dump!(
+                RuntimeError::IndexOutOfBounds::<&str> { index, len },
+                bracket.span.clone()
+            ),
+        }
+    }
+
+    /// Calls `callee` with the values of `args` and `named`
+    fn call_expr(
+        &self,
+        callee: Value,
+        paren: &Token,
+        args: &[Expr],
+        named: &[(Token, Expr)],
+    ) -> Result<Value> {
+        if let Value::Function(fun) = callee {
+            let call = self.arguments(fun, paren, args, named)?;
+            return self.call_function(call);
+        }
+        // a class with an `init` method is called like it, but produces the new instance
+        if let Value::Class(class) = &callee {
+            let instance = Rc::new(Instance::new(Rc::clone(class)));
+            if let Some(init) = instance.method("init") {
+                let call = self.arguments(init, paren, args, named)?;
+                self.call_function(call)?;
+                return Ok(Value::Instance(instance));
+            }
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| self.expression(arg))
+            .collect::<Result<Vec<Value>>>()?;
+        let function = match &callee {
+            Value::NativeFn(native) if !native.params.is_empty() && !named.is_empty() => {
+                let args = self.native_arguments(native, paren, args, named)?;
+                return self.call(callee, paren, args);
+            }
+            Value::NativeFn(native) => native.name.clone(),
+            Value::Class(class) => class.name(),
+            _ => return self.call(callee, paren, args),
+        };
+        match named.first() {
+            // natives without named parameters have nothing to match against
+            Some((name, _)) => dump!(
+                RuntimeError::UnknownParameter {
+                    function,
+                    name: name.lex(),
+                },
+                name.span.clone()
+            ),
+            None => self.call(callee, paren, args),
+        }
+    }
+
+    /// Calls `callee` with `args`, `paren` being the token that closes the argument list
+    fn call(&self, callee: Value, paren: &Token, args: Vec<Value>) -> Result<Value> {
+        match callee {
+            Value::NativeFn(native) => {
+                if !native.arity.contains(&args.len()) {
+                    dump!(
+                        RuntimeError::ArityMismatch::<&str> {
+                            name: native.name.clone(),
+                            expected: native.arity.clone(),
+                            found: args.len(),
+                        },
+                        paren.span.clone()
+                    )
+                }
+                (native.func)(self, paren, args)
+            }
+            Value::Function(fun) => {
+                if !fun.decl.arity().contains(&args.len()) {
+                    dump!(
+                        RuntimeError::ArityMismatch::<&str> {
+                            name: fun.name(),
+                            expected: fun.decl.arity(),
+                            found: args.len(),
+                        },
+                        paren.span.clone()
+                    )
+                }
+
+                let mut args = args.into_iter();
+                let params = fun.decl.params.iter().map(|_| args.next()).collect();
+                self.call_function(Call {
+                    fun,
+                    paren: paren.clone(),
+                    args: params,
+                    rest: args.collect(),
+                })
+            }
+            Value::Class(class) => {
+                let name = class.name();
+                let instance = Rc::new(Instance::new(class));
+                match instance.method("init") {
+                    Some(init) => {
+                        self.call(Value::Function(init), paren, args)?;
+                    }
+                    None if !args.is_empty() => dump!(
+                        RuntimeError::ArityMismatch::<&str> {
+                            name,
+                            expected: 0..=0,
+                            found: args.len(),
+                        },
+                        paren.span.clone()
+                    ),
+                    None => (),
+                }
+                Ok(Value::Instance(instance))
+            }
+            _ => dump!(
+                RuntimeError::NotCallable::<&str>(callee.into()),
+                paren.span.clone()
+            ),
+        }
+    }
+
+    /// Evaluates the arguments of a call to `fun`, `named` arguments being bound to the
+    /// parameters of the same name
+    fn arguments(
+        &self,
+        fun: Function,
+        paren: &Token,
+        args: &[Expr],
+        named: &[(Token, Expr)],
+    ) -> Result<Call> {
+        let decl = &fun.decl;
+        let too_many = args.len() > decl.params.len() && decl.rest.is_none();
+        // which parameters named arguments leave without one is only known once they are bound
+        if too_many || (named.is_empty() && !decl.arity().contains(&args.len())) {
+            return Err(fun.arity_mismatch(paren, args));
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| self.expression(arg))
+            .collect::<Result<Vec<Value>>>()?;
+        let mut args = args.into_iter();
+        let mut params: Vec<Option<Value>> = decl.params.iter().map(|_| args.next()).collect();
+        for (name, arg) in named {
+            let Some(idx) = decl
+                .params
+                .iter()
+                .position(|param| param.lex() == name.lex())
+            else {
+                dump!(
+                    RuntimeError::UnknownParameter {
+                        function: fun.name(),
+                        name: name.lex(),
                     },
-                    TokenKind::Star => match (&left, &right) {
-                        (Value::Integer(a), Value::Integer(b)) => Ok((a * b).into()),
-                        _ => dump!(RuntimeError::InvalidTypes(
-                            op.lex(),
-                            vec![left.into(), right.into()],
-                            vec![(Type::Integer, Type::Integer)],
-                        )),
+                    name.span.clone()
+                )
+            };
+            if params[idx].is_some() {
+                dump!(
+                    RuntimeError::DuplicateArgument(name.lex()),
+                    name.span.clone()
+                )
+            }
+            params[idx] = Some(self.expression(arg)?);
+        }
+
+        Ok(Call {
+            rest: args.collect(),
+            fun,
+            paren: paren.clone(),
+            args: params,
+        })
+    }
+
+    /// Binds the `named` arguments of a call to `native` to the parameters of the same name,
+    /// following the positional `args`
+    fn native_arguments(
+        &self,
+        native: &NativeFn,
+        paren: &Token,
+        args: Vec<Value>,
+        named: &[(Token, Expr)],
+    ) -> Result<Vec<Value>> {
+        let mut params: Vec<Option<Value>> = args.into_iter().map(Some).collect();
+        for (name, arg) in named {
+            let lex = name.lex();
+            let Some(idx) = native.params.iter().position(|param| *param == lex) else {
+                dump!(
+                    RuntimeError::UnknownParameter {
+                        function: native.name.clone(),
+                        name: lex,
                     },
-                    TokenKind::Plus => match (&left, &right) {
-                        (Value::Integer(a), Value::Integer(b)) => Ok((a + b).into()),
-                        (Value::String(a), Value::String(b)) => Ok(format!("{a}{b}").into()),
-                        _ => dump!(RuntimeError::InvalidTypes(
-                            op.lex(),
-                            vec![left.into(), right.into()],
-                            vec![(Type::Integer, Type::Integer), (Type::String, Type::String)],
-                        )),
+                    name.span.clone()
+                )
+            };
+            if params.len() <= idx {
+                params.resize(idx + 1, None);
+            }
+            if params[idx].is_some() {
+                dump!(RuntimeError::DuplicateArgument(lex), name.span.clone())
+            }
+            params[idx] = Some(self.expression(arg)?);
+        }
+
+        params
+            .into_iter()
+            .enumerate()
+            .map(|(idx, arg)| match arg {
+                Some(arg) => Ok(arg),
+                // only parameters before one that a named argument was bound to can be missing
+                None => dump!(
+                    RuntimeError::MissingArgument {
+                        function: native.name.clone(),
+                        param: native.params[idx].to_string(),
                     },
-                    _ => dump!(RuntimeError::InvalidOperator(
-                        op.lex(),
-                        vec!['+', '/', '-', '*']
-                    )),
+                    paren.span.clone()
+                ),
+            })
+            .collect()
+    }
+
+    /// Executes the body of a function, unless [`Options::max_depth`] calls are already in
+    /// progress
+    fn call_function(&self, call: Call) -> Result<Value> {
+        let depth = self.depth.get();
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            dump!(
+                RuntimeError::StackOverflow::<&str> { depth },
+                call.paren.span
+            )
+        }
+
+        self.depth.set(depth + 1);
+        let value = self.execute_call(call);
+        self.depth.set(depth);
+        value
+    }
+
+    /// Executes the body of a function
+    ///
+    /// A call that the body makes in tail position is made once the body has returned, rather
+    /// than from within it, so that tail recursion runs in constant stack space.
+    fn execute_call(&self, call: Call) -> Result<Value> {
+        let mut call = call;
+        loop {
+            let Call {
+                fun,
+                paren,
+                args,
+                rest,
+            } = call;
+
+            let guarded = self.guarded.replace(0);
+            let frame = self.frame.replace(self.deferred.borrow().len());
+            let flow = self
+                .bind(&fun, &paren, args, rest)
+                .map_err(|err| vec![err])
+                .and_then(|env| self.execute_block(&fun.decl.body, env));
+            self.guarded.set(guarded);
+            self.frame.set(frame);
+
+            call = match flow {
+                Ok(Flow::Return(value)) => return Ok(value),
+                Ok(Flow::Normal) => return Ok(Value::Null),
+                Ok(Flow::TailCall(next)) => *next,
+                Err(errs) => {
+                    return Err(trace(
+                        first_error(errs),
+                        Frame {
+                            name: fun.name(),
+                            call: paren.span,
+                        },
+                    ))
                 }
+            };
+        }
+    }
+
+    /// Whether a call made by the statement being executed can be left to the caller of the
+    /// function executing it: nothing is left for the function to do once the call returns, as
+    /// no `try` or `with` encloses the statement and no expressions are deferred
+    fn in_tail_position(&self) -> bool {
+        self.guarded.get() == 0
+            && self.deferred.borrow()[self.frame.get()..]
+                .iter()
+                .all(Vec::is_empty)
+    }
+
+    /// `return callee(args)`, which returns the call for the caller to make if it is a call to a
+    /// function declared in atium, in tail position
+    fn tail_call(
+        &self,
+        callee: &Expr,
+        paren: &Token,
+        args: &[Expr],
+        named: &[(Token, Expr)],
+    ) -> Result<Flow> {
+        match self.expression(callee)? {
+            Value::Function(fun) if self.in_tail_position() => {
+                let call = self.arguments(fun, paren, args, named)?;
+                Ok(Flow::TailCall(Box::new(call)))
             }
+            callee => self.call_expr(callee, paren, args, named).map(Flow::Return),
         }
     }
 
+    /// Creates the scope that the body of `fun` executes in, binding each parameter to its
+    /// argument or, if it was not passed, its default value
+    fn bind(
+        &self,
+        fun: &Function,
+        paren: &Token,
+        args: Vec<Option<Value>>,
+        rest: Vec<Value>,
+    ) -> Result<Env> {
+        let decl = &fun.decl;
+        let env = Rc::new(RefCell::new(Env::with_parent(Rc::clone(&fun.closure))));
+        let mut args = args.into_iter();
+
+        // defaults are evaluated in the scope being created, so they can refer to the parameters
+        // before them
+        let prev_env = self.env.replace(Rc::clone(&env));
+        let bound: Result<()> = decl.params.iter().enumerate().try_for_each(|(idx, param)| {
+            let value = match (args.next().flatten(), decl.default(idx)) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.expression(default)?,
+                (None, None) => dump!(
+                    RuntimeError::MissingArgument {
+                        function: fun.name(),
+                        param: param.lex(),
+                    },
+                    paren.span.clone()
+                ),
+            };
+            env.borrow_mut().define(param.clone(), Some(value));
+            Ok(())
+        });
+        self.env.replace(prev_env);
+        bound?;
+
+        if let Some(name) = &decl.rest {
+            env.borrow_mut()
+                .define(name.clone(), Some(Value::List(rest)));
+        }
+        // a default may have captured the scope, in which case the body gets a scope of its own
+        Ok(Rc::try_unwrap(env).map_or_else(Env::with_parent, RefCell::into_inner))
+    }
+
+    /// Prints the value of `expr`, cut off after [`Options::max_print`] characters
     fn print(&self, expr: &Expr) -> Result<()> {
-        println!("{}", self.expression(expr)?);
+        let value = self.expression(expr)?.to_string();
+        self.print_str(&value);
+        Ok(())
+    }
+
+    /// Evaluates a top level expression statement, printing its value unless it is `Null`.
+    /// Strings are quoted so that they are distinguishable from other values.
+    fn echo(&self, expr: &Expr) -> Result<()> {
+        match self.expression(expr)? {
+            Value::Null => (),
+            value => self.print_str(&value.nested()),
+        }
         Ok(())
     }
+
+    /// Prints `value`, cutting it off if it is longer than the print limit
+    fn print_str(&self, value: &str) {
+        match self.options.max_print {
+            Some(max) if value.chars().count() > max => {
+                let shown = value.chars().take(max).collect::<String>();
+                let hidden = value.chars().count() - max;
+                self.options.output.writeln(&format!(
+                    "{shown}… ({hidden} more characters, raise the print limit of {max} to see \
+                     them)"
+                ));
+            }
+            _ => self.options.output.writeln(value),
+        }
+    }
+}
+
+/// The elements of the tuple, list or range `value`, of which there must be one for each of
+/// `names`
+fn unpack(value: Value, names: &[Token]) -> Result<Vec<Value>> {
+    let span = names[0].span.clone();
+    let elements = match value {
+        Value::Tuple(elements) | Value::List(elements) => elements,
+        range @ Value::Range(..) => iterate::elements(range, span.clone())?.collect(),
+        value => dump!(
+            RuntimeError::InvalidType::<&str>(
+                value.into(),
+                vec![Type::Tuple, Type::List, Type::Range]
+            ),
+            span
+        ),
+    };
+
+    if elements.len() != names.len() {
+        dump!(
+            RuntimeError::DestructureMismatch::<&str> {
+                expected: names.len(),
+                found: elements.len(),
+            },
+            span
+        );
+    }
+    Ok(elements)
+}
+
+/// Only the first error is surfaced to the caller, the rest are most likely cascading from it
+fn first_error(errs: Vec<Report>) -> Report {
+    errs.into_iter()
+        .next()
+        .expect("a failing stage produces at least one error")
 }
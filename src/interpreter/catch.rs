@@ -0,0 +1,147 @@
+//! Throwing and catching errors
+//!
+//! A thrown value is caught unchanged, whereas any other error is caught as a map describing it.
+use std::rc::Rc;
+
+use color_eyre::{Report, Result};
+
+use super::{first_error, Flow, Instance, Interpreter};
+use crate::{
+    ast::{Expr, Stmt},
+    dump,
+    environment::Env,
+    error::{describe, Described, RuntimeError, Span},
+    token::{Map, Token, Type, Value},
+};
+
+impl Interpreter {
+    /// Executes `body`, leaving it at the first error, which is then bound to `name` while
+    /// `handler` executes
+    pub(super) fn try_catch(
+        &self,
+        body: &[Stmt],
+        name: &Token,
+        handler: &[Stmt],
+    ) -> Result<Flow, Vec<Report>> {
+        self.catching.set(self.catching.get() + 1);
+        self.guarded.set(self.guarded.get() + 1);
+        let flow = self.execute_block(body, Env::with_parent(self.scope()));
+        self.guarded.set(self.guarded.get() - 1);
+        self.catching.set(self.catching.get() - 1);
+
+        match flow {
+            Ok(flow) => Ok(flow),
+            Err(errs) => {
+                let described = describe(&first_error(errs));
+                let caught = described
+                    .thrown
+                    .map_or_else(|| error_value(described), |id| self.take_thrown(id));
+
+                let mut env = Env::with_parent(self.scope());
+                env.define(name.clone(), Some(caught));
+                self.execute_block(handler, env)
+            }
+        }
+    }
+
+    /// Removes the thrown value at `id` now that it has been caught
+    ///
+    /// Any values thrown after it were replaced by it while unwinding, as by a `defer` that
+    /// throws, so can never be caught and are removed too.
+    fn take_thrown(&self, id: usize) -> Value {
+        let mut thrown = self.thrown.borrow_mut();
+        let caught = thrown.drain(id..).next();
+        caught.expect("thrown values are held until they are caught")
+    }
+
+    /// Raises the value of `value` as an error
+    pub(super) fn throw(&self, keyword: &Token, value: &Expr) -> Result<Flow> {
+        let value = self.expression(value)?;
+        let (ty, payload) = match &value {
+            Value::Instance(instance) => (
+                instance.class.name(),
+                self.describe_instance(instance, keyword)?,
+            ),
+            _ => (Type::from(value.clone()).to_string(), value.nested()),
+        };
+
+        let mut thrown = self.thrown.borrow_mut();
+        thrown.push(value);
+        dump!(
+            RuntimeError::Thrown::<&str> {
+                ty,
+                payload,
+                id: thrown.len() - 1,
+            },
+            keyword.span.clone()
+        )
+    }
+
+    /// What is reported of a thrown instance if it is not caught: its `message` field or getter,
+    /// what its `message` or `toString` method returns, or else its fields, as in `MyErr(code: 3)`
+    fn describe_instance(&self, instance: &Rc<Instance>, keyword: &Token) -> Result<String> {
+        if let Some(message) = instance.fields.borrow().get("message") {
+            return Ok(message.to_string());
+        }
+        let method = |name| {
+            instance.getter(name).or_else(|| {
+                instance
+                    .method(name)
+                    .filter(|method| method.decl.arity().contains(&0))
+            })
+        };
+        if let Some(method) = method("message").or_else(|| method("toString")) {
+            let message = self.call(Value::Function(method), keyword, vec![])?;
+            return Ok(message.to_string());
+        }
+
+        let fields = instance.fields.borrow();
+        let mut names = fields.keys().collect::<Vec<_>>();
+        names.sort();
+        let fields = names
+            .into_iter()
+            .map(|name| format!("{name}: {}", fields[name].nested()))
+            .collect::<Vec<_>>();
+        Ok(format!("{}({})", instance.class.name(), fields.join(", ")))
+    }
+}
+
+/// Describes an error as a map of its `kind`, `message`, `line`, `column` and `stack`, which is
+/// a list of the calls in progress when it occurred, innermost first
+fn error_value(described: Described) -> Value {
+    let stack = described
+        .frames
+        .iter()
+        .map(|frame| {
+            let (line, column) = location(Some(&frame.call));
+            Value::Map(Map::from_iter([
+                (string("function"), string(&frame.name)),
+                (string("line"), line),
+                (string("column"), column),
+            ]))
+        })
+        .collect();
+
+    let (line, column) = location(described.span.as_ref());
+    Value::Map(Map::from_iter([
+        (string("kind"), string(described.kind)),
+        (string("message"), Value::String(described.message)),
+        (string("line"), line),
+        (string("column"), column),
+        (string("stack"), Value::List(stack)),
+    ]))
+}
+
+/// The line and column of `span`, which are `nil` if it is not known
+fn location(span: Option<&Span>) -> (Value, Value) {
+    span.map_or((Value::Null, Value::Null), |span| {
+        (
+            Value::Integer(span.line.0.into()),
+            Value::Integer(span.column.0.into()),
+        )
+    })
+}
+
+fn string(string: &str) -> Value {
+    Value::String(string.to_string())
+}
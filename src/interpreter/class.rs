@@ -0,0 +1,194 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use super::function::Function;
+use crate::{
+    ast::Signature,
+    token::{Token, Value},
+};
+
+/// A class declared in atium, which creates instances when called
+pub struct Class {
+    pub name: Token,
+    /// the `///` comments preceding the declaration
+    pub doc: Option<String>,
+    pub methods: HashMap<String, Function>,
+    /// the methods declared `static`, which are looked up on the class itself
+    pub statics: HashMap<String, Function>,
+    /// the methods called when a property is read, in place of looking it up
+    pub getters: HashMap<String, Function>,
+    /// the methods called when a property is assigned to, in place of setting it
+    pub setters: HashMap<String, Function>,
+    /// the traits that the class declares it implements
+    pub traits: Vec<Rc<Trait>>,
+}
+
+impl Class {
+    pub fn name(&self) -> String {
+        self.name.lex()
+    }
+
+    /// The names of the class's methods, in alphabetical order
+    pub fn method_names(&self) -> Vec<String> {
+        sorted(&self.methods)
+    }
+
+    /// Whether the class declares that it implements `r#trait`
+    pub fn implements(&self, r#trait: &Rc<Trait>) -> bool {
+        self.traits.iter().any(|other| Rc::ptr_eq(other, r#trait))
+    }
+
+    /// The names of the class's static methods, in alphabetical order
+    pub fn static_names(&self) -> Vec<String> {
+        sorted(&self.statics)
+    }
+}
+
+/// A set of methods that the classes implementing it must have
+pub struct Trait {
+    pub name: Token,
+    /// the `///` comments preceding the declaration
+    pub doc: Option<String>,
+    pub methods: Vec<Signature>,
+}
+
+impl Trait {
+    pub fn name(&self) -> String {
+        self.name.lex()
+    }
+}
+
+/// An object created by calling a class
+pub struct Instance {
+    pub class: Rc<Class>,
+    /// the properties that have been set on the instance, which shadow its methods
+    pub fields: RefCell<HashMap<String, Value>>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Self {
+            class,
+            fields: RefCell::default(),
+        }
+    }
+
+    /// The value of the field or method called `name`
+    pub fn get(self: &Rc<Self>, name: &str) -> Option<Value> {
+        self.fields
+            .borrow()
+            .get(name)
+            .cloned()
+            .or_else(|| self.method(name).map(Value::Function))
+    }
+
+    /// The method called `name`, with `this` bound to the instance
+    pub fn method(self: &Rc<Self>, name: &str) -> Option<Function> {
+        self.bound(&self.class.methods, name)
+    }
+
+    /// The getter of the property `name`, with `this` bound to the instance
+    pub fn getter(self: &Rc<Self>, name: &str) -> Option<Function> {
+        self.bound(&self.class.getters, name)
+    }
+
+    /// The setter of the property `name`, with `this` bound to the instance
+    pub fn setter(self: &Rc<Self>, name: &str) -> Option<Function> {
+        self.bound(&self.class.setters, name)
+    }
+
+    fn bound(self: &Rc<Self>, methods: &HashMap<String, Function>, name: &str) -> Option<Function> {
+        let method = methods.get(name)?;
+        Some(method.bind(Rc::clone(self)))
+    }
+}
+
+fn sorted(methods: &HashMap<String, Function>) -> Vec<String> {
+    let mut names = methods.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Class({})", self.name())
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<class {}>", self.name())
+    }
+}
+
+impl fmt::Debug for Trait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Trait({})", self.name())
+    }
+}
+
+impl fmt::Display for Trait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<trait {}>", self.name())
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instance({})", self.class.name())
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} instance>", self.class.name())
+    }
+}
+
+/// Classes, traits and instances are only ever equal to themselves
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Class {}
+
+impl Hash for Class {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self, state);
+    }
+}
+
+impl PartialEq for Trait {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Trait {}
+
+impl Hash for Trait {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self, state);
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Instance {}
+
+impl Hash for Instance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self, state);
+    }
+}
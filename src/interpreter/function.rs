@@ -0,0 +1,125 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use color_eyre::Report;
+
+use crate::{
+    ast::{Expr, FunDecl},
+    diagnostic,
+    environment::Env,
+    error::RuntimeError,
+    token::{Token, TokenKind, Value},
+};
+
+use super::{class::Instance, native::ident};
+
+/// A function declared in atium, closing over the scope that it was declared in
+#[derive(Clone)]
+pub struct Function {
+    pub decl: Rc<FunDecl>,
+    pub closure: Rc<RefCell<Env>>,
+}
+
+impl Function {
+    pub fn name(&self) -> String {
+        self.decl.name.lex()
+    }
+
+    /// The method with `this` defined as `instance`, in a scope of its own between the method's
+    /// closure and its body
+    #[must_use]
+    pub fn bind(&self, instance: Rc<Instance>) -> Self {
+        let mut env = Env::with_parent(Rc::clone(&self.closure));
+        env.define(this(), Some(Value::Instance(instance)));
+        Self {
+            decl: Rc::clone(&self.decl),
+            closure: Rc::new(RefCell::new(env)),
+        }
+    }
+
+    /// Describes a call with the wrong number of arguments, pointing out the declaration and the
+    /// arguments or parameters that are left over
+    pub(super) fn arity_mismatch(&self, paren: &Token, args: &[Expr]) -> Report {
+        let params = &self.decl.params;
+        let mut diagnostic = diagnostic!(
+            RuntimeError::ArityMismatch::<&str> {
+                name: self.name(),
+                expected: self.decl.arity(),
+                found: args.len(),
+            },
+            paren.span.clone()
+        )
+        .note(
+            format!(
+                "{} is declared with the parameters ({})",
+                self.name(),
+                self.decl.signature()
+            ),
+            self.decl.name.span.clone(),
+        );
+
+        // a rest parameter takes any arguments left over
+        let unused = if self.decl.rest.is_some() {
+            args.len()
+        } else {
+            params.len()
+        };
+        for (idx, arg) in args.iter().enumerate().skip(unused) {
+            if let Some(tok) = arg.first_token() {
+                diagnostic = diagnostic.note(
+                    format!("argument {} has no parameter", idx + 1),
+                    tok.span.clone(),
+                );
+            }
+        }
+        for param in params.iter().take(self.decl.required()).skip(args.len()) {
+            diagnostic = diagnostic.note(
+                format!("no argument was given for {}", param.lex()),
+                param.span.clone(),
+            );
+        }
+
+        diagnostic.into()
+    }
+}
+
+/// The closure is left out as it may (indirectly) contain the function itself
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Function({})", self.name())
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.name())
+    }
+}
+
+/// Functions are only equal if they were created by the same declaration in the same scope
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.decl, &other.decl) && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+}
+
+impl Eq for Function {}
+
+impl Hash for Function {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.decl).hash(state);
+        Rc::as_ptr(&self.closure).hash(state);
+    }
+}
+
+/// The name that the instance a method is bound to is defined under
+pub(super) fn this() -> Token {
+    Token {
+        kind: TokenKind::This,
+        ..ident("this")
+    }
+}
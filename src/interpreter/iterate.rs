@@ -0,0 +1,102 @@
+//! Iterating over collections with `for name in iterable`
+//!
+//! Lists and tuples are iterated over element by element, strings grapheme by grapheme and maps
+//! key by key, in the order that the keys were inserted, as are sets element by element. Ranges
+//! created by `range` produce their integers one at a time, so are never held in memory.
+use color_eyre::{Report, Result};
+use std::{ops, vec};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Flow, Interpreter};
+use crate::{
+    ast::{Expr, Stmt},
+    dump,
+    environment::Env,
+    error::{RuntimeError, Span},
+    token::{map, set, Token, Type, Value},
+};
+
+impl Interpreter {
+    /// Executes `body` once for each element of `iterable`, in a new scope each time so that
+    /// closures capture the element of their own iteration
+    pub(super) fn for_in(
+        &self,
+        keyword: &Token,
+        name: &Token,
+        iterable: &Expr,
+        body: &[Stmt],
+    ) -> Result<Flow, Vec<Report>> {
+        let value = self.expression(iterable).map_err(|err| vec![err])?;
+        let span = iterable
+            .first_token()
+            .map_or_else(|| keyword.span.clone(), |tok| tok.span.clone());
+        let elements = elements(value, span).map_err(|err| vec![err])?;
+
+        for element in elements {
+            let mut env = Env::with_parent(self.scope());
+            env.define(name.clone(), Some(element));
+            match self.execute_block(body, env)? {
+                Flow::Normal => (),
+                unwind => return Ok(unwind),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+}
+
+/// The elements that iterating over `value` produces, erroring at `span` if it cannot be iterated
+/// over
+pub(super) fn elements(value: Value, span: Span) -> Result<Elements> {
+    match value {
+        Value::List(list) | Value::Tuple(list) => Ok(Elements::List(list.into_iter())),
+        Value::Range(start, end) => Ok(Elements::Range(start..end)),
+        Value::String(string) => Ok(Elements::String { string, offset: 0 }),
+        Value::Map(map) => Ok(Elements::Map(map.into_keys())),
+        Value::Set(set) => Ok(Elements::Set(set.into_values())),
+        value => dump!(
+            RuntimeError::NotIterable::<&str>(
+                value.into(),
+                vec![
+                    Type::List,
+                    Type::Tuple,
+                    Type::Range,
+                    Type::Map,
+                    Type::Set,
+                    Type::String
+                ]
+            ),
+            span
+        ),
+    }
+}
+
+/// The elements of an iterable value, produced as they are needed
+pub(super) enum Elements {
+    List(vec::IntoIter<Value>),
+    Range(ops::Range<i128>),
+    /// the graphemes of `string` after the first `offset` bytes
+    String {
+        string: String,
+        offset: usize,
+    },
+    Map(map::IntoKeys),
+    Set(set::IntoValues),
+}
+
+impl Iterator for Elements {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            Self::List(list) => list.next(),
+            Self::Range(range) => range.next().map(Value::Integer),
+            Self::String { string, offset } => {
+                let grapheme = string[*offset..].graphemes(true).next()?.to_string();
+                *offset += grapheme.len();
+                Some(Value::String(grapheme))
+            }
+            Self::Map(keys) => keys.next(),
+            Self::Set(set) => set.next(),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use color_eyre::Result;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+    rc::Rc,
+};
+
+use std::any::Any;
+
+use super::{iterate, Interpreter};
+use crate::{
+    dump,
+    environment::Env,
+    error::{Column, Line, RuntimeError, Span},
+    token::{Token, TokenKind, Type, Value},
+};
+
+mod bench;
+mod delimited;
+mod encoding;
+mod eval;
+#[cfg(feature = "hash")]
+mod hash;
+mod introspect;
+mod memo;
+mod number;
+mod parse;
+mod path;
+pub(super) mod set;
+pub(super) mod string;
+mod template;
+#[cfg(feature = "units")]
+mod units;
+
+/// The signature of a function implemented in Rust. Receives the token of the call site, which is
+/// used to attribute errors, and the evaluated arguments.
+type NativeImpl = dyn Fn(&Interpreter, &Token, Vec<Value>) -> Result<Value>;
+
+/// A function implemented in Rust that can be called from atium
+///
+/// Hosts can give programs functions of their own through [`Options::natives`].
+///
+/// [`Options::natives`]: crate::atium::Options::natives
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    /// the number of arguments that the function accepts
+    pub arity: RangeInclusive<usize>,
+    pub(crate) func: Rc<NativeImpl>,
+    /// state that the function carries with it, which other natives may inspect
+    pub(crate) state: Option<Rc<dyn Any>>,
+    /// the signature of the function followed by a description of it, shown by `help`
+    pub doc: Option<&'static str>,
+    /// the names of the parameters, which named arguments are bound to. Functions without any
+    /// only take positional arguments.
+    pub params: &'static [&'static str],
+}
+
+impl NativeFn {
+    pub fn new<F>(name: &str, arity: RangeInclusive<usize>, func: F) -> Self
+    where
+        F: Fn(&Interpreter, &Token, Vec<Value>) -> Result<Value> + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            arity,
+            func: Rc::new(func),
+            state: None,
+            doc: None,
+            params: &[],
+        }
+    }
+
+    /// Documents the function, see [`NativeFn::doc`]
+    #[must_use]
+    pub const fn with_doc(mut self, doc: &'static str) -> Self {
+        self.doc = Some(doc);
+        self
+    }
+
+    /// Names the parameters of the function, see [`NativeFn::params`]
+    #[must_use]
+    pub const fn with_params(mut self, params: &'static [&'static str]) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Attaches state to the function that can later be retrieved by [`NativeFn::state`]
+    pub(crate) fn with_state(mut self, state: Rc<dyn Any>) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Retrieves the function's state, if it is of type `T`
+    pub(crate) fn state<T: 'static>(&self) -> Option<Rc<T>> {
+        self.state.clone()?.downcast().ok()
+    }
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFn({})", self.name)
+    }
+}
+
+impl fmt::Display for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// Native functions are only ever equal to themselves
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.func, &other.func)
+    }
+}
+
+impl Eq for NativeFn {}
+
+impl Hash for NativeFn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.func).cast::<()>().hash(state);
+    }
+}
+
+/// Defines all of the native functions in `env`
+pub fn define_natives(env: &mut Env) {
+    let natives = [
+        NativeFn::new("clock", 0..=0, bench::clock)
+            .with_doc("clock()\nThe number of seconds since the Unix epoch, as a float"),
+        NativeFn::new("bench", 2..=2, bench::bench).with_doc(
+            "bench(fn, iterations)\nCalls fn iterations times, returning a map of the min, max, \
+             mean and stddev of the timings in nanoseconds",
+        ),
+        NativeFn::new("eval", 1..=2, eval::eval)
+            .with_params(&["source", "isolated"])
+            .with_doc(
+                "eval(source, isolated: false)\nRuns source, returning the value of its final \
+                 expression. If isolated is true, or the map {\"isolated\": true} is passed, it \
+                 runs in a fresh global scope and cannot see or change the caller's variables",
+            ),
+        NativeFn::new("evalAst", 1..=1, eval::eval_ast)
+            .with_doc("evalAst(ast)\nRuns a syntax tree created by quote"),
+        NativeFn::new("memoize", 1..=1, memo::memoize).with_doc(
+            "memoize(fn)\nWraps fn so that repeated calls with the same arguments are cached",
+        ),
+        NativeFn::new("cacheStats", 1..=1, memo::cache_stats)
+            .with_doc("cacheStats(fn)\nThe hits, misses and entries of the cache of a function created by memoize"),
+        NativeFn::new("len", 1..=1, string::len).with_doc(
+            "len(value)\nThe number of graphemes in a string, or elements in a collection",
+        ),
+        NativeFn::new("render", 2..=2, template::render).with_doc(
+            "render(template, vars)\nFills in the {{name}}, {{#for x in xs}} and {{#if name}} \
+             tags of template with the fields of an instance or entries of a map",
+        ),
+        NativeFn::new("set", 1..=1, set::set).with_doc(
+            "set(iterable)\nA set of the elements of a list, string or map, in the order they \
+             are iterated over",
+        ),
+        NativeFn::new("str", 1..=1, string::str)
+            .with_doc("str(value)\nConverts value to a string, as print would display it"),
+        NativeFn::new("formatNumber", 2..=2, number::format_number).with_doc(
+            "formatNumber(number, digits)\nFormats number with digits digits after the \
+             decimal point",
+        ),
+        NativeFn::new("parseNumber", 1..=1, number::parse_number)
+            .with_doc("parseNumber(string)\nParses a string written like a number literal"),
+        NativeFn::new("parseJson", 1..=1, parse::parse_json).with_doc(
+            "parseJson(string)\nParses JSON into maps, lists, strings, numbers, booleans and nil",
+        ),
+        NativeFn::new("range", 1..=2, number::range).with_doc(
+            "range(start?, end)\nThe integers from start, or 0, up to but not including end, \
+             produced one at a time as they are iterated over",
+        ),
+        NativeFn::new("help", 1..=1, introspect::help).with_doc(
+            "help(value)\nDescribes a function or class, or the methods of any other value",
+        ),
+        NativeFn::new("dir", 1..=1, introspect::dir)
+            .with_doc("dir(value)\nLists the names of the methods of value"),
+        NativeFn::new("readCsv", 1..=2, delimited::read_csv).with_doc(
+            "readCsv(path, delimiter?)\nReads a file of comma, or for .tsv files tab, separated \
+             values into a list of maps from the names in its header row to each column's string",
+        ),
+        NativeFn::new("writeCsv", 2..=3, delimited::write_csv).with_doc(
+            "writeCsv(path, rows, delimiter?)\nWrites a list of maps as comma separated values, \
+             headed by every key that the maps have",
+        ),
+        NativeFn::new("glob", 1..=1, path::glob).with_doc(
+            "glob(pattern)\nThe paths matching pattern, such as \"src/**/*.at\", in alphabetical \
+             order",
+        ),
+        NativeFn::new("pathJoin", 1..=usize::MAX, path::path_join)
+            .with_doc("pathJoin(parts...)\nJoins parts into a path with the platform's separator"),
+        NativeFn::new("basename", 1..=1, path::basename)
+            .with_doc("basename(path)\nThe last part of path, such as \"main.at\""),
+        NativeFn::new("extension", 1..=1, path::extension).with_doc(
+            "extension(path)\nThe extension of path without its dot, or nil if it has none",
+        ),
+        NativeFn::new("base64Encode", 1..=1, encoding::base64_encode)
+            .with_doc("base64Encode(data)\nEncodes a string, as UTF-8, or list of bytes as base64"),
+        NativeFn::new("base64Decode", 1..=1, encoding::base64_decode)
+            .with_doc("base64Decode(string)\nDecodes base64 into a list of bytes"),
+        NativeFn::new("hexEncode", 1..=1, encoding::hex_encode).with_doc(
+            "hexEncode(data)\nEncodes a string, as UTF-8, or list of bytes as lowercase hex",
+        ),
+        NativeFn::new("hexDecode", 1..=1, encoding::hex_decode)
+            .with_doc("hexDecode(string)\nDecodes hex, in either case, into a list of bytes"),
+        NativeFn::new("typeof", 1..=1, introspect::type_of).with_doc(
+            "typeof(value)\nThe name of the type of value, such as \"Integer\" or \"Instance\"",
+        ),
+    ];
+
+    for native in natives {
+        env.define_native(native);
+    }
+    #[cfg(feature = "decimal")]
+    env.define_native(NativeFn::new("decimal", 1..=1, number::decimal).with_doc(
+        "decimal(value)\nConverts an integer, float or string to a decimal, floats keeping the \
+         digits they are displayed with",
+    ));
+    #[cfg(feature = "hash")]
+    {
+        env.define_native(NativeFn::new("sha256", 1..=1, hash::sha256).with_doc(
+            "sha256(data)\nThe SHA-256 digest of a string or list of bytes, in hexadecimal",
+        ));
+        env.define_native(
+            NativeFn::new("md5", 1..=1, hash::md5)
+                .with_doc("md5(data)\nThe MD5 digest of a string or list of bytes, in hexadecimal"),
+        );
+        env.define_native(NativeFn::new("crc32", 1..=1, hash::crc32).with_doc(
+            "crc32(data)\nThe CRC-32 checksum of a string or list of bytes, in hexadecimal",
+        ));
+    }
+    #[cfg(feature = "toml")]
+    env.define_native(NativeFn::new("parseToml", 1..=1, parse::parse_toml).with_doc(
+        "parseToml(string)\nParses TOML into maps, lists, strings, numbers and booleans, dates \
+         and times being strings",
+    ));
+    #[cfg(feature = "yaml")]
+    env.define_native(
+        NativeFn::new("parseYaml", 1..=1, parse::parse_yaml).with_doc(
+            "parseYaml(string)\nParses YAML into maps, lists, strings, numbers, booleans and nil",
+        ),
+    );
+    #[cfg(feature = "units")]
+    {
+        env.define_native(NativeFn::new("quantity", 2..=2, units::quantity).with_doc(
+            "quantity(number, unit)\nnumber measured in unit, which may be compound, as in \
+             \"m/s^2\"",
+        ));
+        env.define_native(
+            NativeFn::new("convert", 2..=2, units::convert)
+                .with_doc("convert(quantity, unit)\nThe number of unit in quantity, as a float"),
+        );
+    }
+}
+
+/// Errors unless the host has granted programs access to files, which the native `name` needs
+fn require_fs(interpreter: &Interpreter, name: &str, paren: &Token) -> Result<()> {
+    if interpreter.options.capabilities.fs {
+        return Ok(());
+    }
+    dump!(
+        RuntimeError::CapabilityDenied::<&str> {
+            function: name.to_string(),
+            capability: "fs",
+        },
+        paren.span.clone()
+    )
+}
+
+/// The bytes of a string, as UTF-8, or of a list or range of integers from 0 to 255, such as
+/// `s.bytes()` returns, which the native `name` takes
+fn bytes(name: &str, value: Option<Value>, paren: &Token) -> Result<Vec<u8>> {
+    match value {
+        Some(Value::String(string)) => Ok(string.into_bytes()),
+        Some(list @ (Value::List(_) | Value::Range(..))) => {
+            iterate::elements(list, paren.span.clone())?
+                .map(|value| {
+                    let byte = match &value {
+                        Value::Integer(int) => u8::try_from(*int).ok(),
+                        _ => None,
+                    };
+                    match byte {
+                        Some(byte) => Ok(byte),
+                        None => dump!(
+                            RuntimeError::InvalidArgument(
+                                name.to_string(),
+                                format!(
+                                    "{value} in a list of bytes, expected an integer from 0 to 255"
+                                )
+                            ),
+                            paren.span.clone()
+                        ),
+                    }
+                })
+                .collect()
+        }
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String, Type::List, Type::Range]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// Creates an identifier token that does not originate from source code
+pub fn ident(name: &str) -> Token {
+    Token::new(
+        TokenKind::Identifier,
+        None,
+        Span {
+            line: Line(0),
+            column: Column(0),
+            start: 0,
+            end: 0,
+            file: None,
+            lex: name.to_string(),
+        },
+    )
+}
@@ -0,0 +1,77 @@
+use color_eyre::Result;
+use ordered_float::OrderedFloat;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Map, Token, Type, Value},
+};
+
+/// `clock()`
+///
+/// The number of seconds since the Unix epoch, with sub-second precision, for timing programs
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn clock(_: &Interpreter, _: &Token, _: Vec<Value>) -> Result<Value> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(Value::Float(OrderedFloat(since_epoch.as_secs_f64())))
+}
+
+/// `bench(fn, iterations)`
+///
+/// Calls `fn` without arguments `iterations` times, timing each call. Returns a map of the
+/// `min`, `max`, `mean` and `stddev` of the timings, in nanoseconds.
+#[allow(clippy::cast_precision_loss)] // nobody waits for 2^52 iterations
+pub(super) fn bench(interpreter: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let fun = args.next().expect("arity is checked before calling");
+    let iterations = match args.next() {
+        Some(Value::Integer(iterations)) if iterations > 0 => iterations,
+        Some(Value::Integer(iterations)) => dump!(
+            RuntimeError::InvalidArgument(
+                String::from("bench"),
+                format!("{iterations} iterations, expected at least 1")
+            ),
+            paren.span.clone()
+        ),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::Integer]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    let mut timings = vec![];
+    for _ in 0..iterations {
+        let start = Instant::now();
+        interpreter.call(fun.clone(), paren, vec![])?;
+        timings.push(start.elapsed().as_secs_f64() * 1e9);
+    }
+
+    let count = timings.len() as f64;
+    let mean = timings.iter().sum::<f64>() / count;
+    let variance = timings.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / count;
+    let stats = [
+        ("min", timings.iter().copied().fold(f64::INFINITY, f64::min)),
+        ("max", timings.iter().copied().fold(0.0, f64::max)),
+        ("mean", mean),
+        ("stddev", variance.sqrt()),
+    ];
+
+    Ok(Value::Map(
+        stats
+            .into_iter()
+            .map(|(stat, nanos)| {
+                (
+                    Value::from(stat.to_string()),
+                    Value::Float(OrderedFloat(nanos)),
+                )
+            })
+            .collect::<Map>(),
+    ))
+}
@@ -0,0 +1,156 @@
+//! Reading and writing files of delimiter separated values, such as CSV and TSV
+use color_eyre::{Report, Result};
+use indexmap::IndexSet;
+use std::{fmt, path::Path};
+
+use super::require_fs;
+use crate::{
+    diagnostic, dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Map, Token, Type, Value},
+};
+
+/// `readCsv(path, delimiter?)`
+///
+/// Reads the file at `path` into a list with a map for each row after the header, from the names
+/// in the header to the strings in that row. Values are separated by `delimiter`, which if not
+/// given is a tab for `.tsv` files and a comma otherwise, and may be quoted.
+pub(super) fn read_csv(
+    interpreter: &Interpreter,
+    paren: &Token,
+    args: Vec<Value>,
+) -> Result<Value> {
+    require_fs(interpreter, "readCsv", paren)?;
+    let mut args = args.into_iter();
+    let path = string(args.next(), paren)?;
+    let delimiter = delimiter("readCsv", &path, args.next(), paren)?;
+
+    let io = |err: csv::Error| io_error(&path, &err, paren);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_path(&path)
+        .map_err(io)?;
+    let header = reader.headers().map_err(io)?.clone();
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.map_err(io)?;
+        let row = header
+            .iter()
+            .zip(record.iter())
+            .map(|(name, value)| (Value::String(name.into()), Value::String(value.into())))
+            .collect::<Map>();
+        rows.push(Value::Map(row));
+    }
+    Ok(Value::List(rows))
+}
+
+/// `writeCsv(path, rows, delimiter?)`
+///
+/// Writes the list of maps `rows` to the file at `path`, separated as `readCsv` would expect,
+/// headed by every key of the maps in the order that they are first seen. A row without one of
+/// the keys leaves its column empty, as does `nil`, and values are written as `print` would
+/// display them, quoted where needed.
+pub(super) fn write_csv(
+    interpreter: &Interpreter,
+    paren: &Token,
+    args: Vec<Value>,
+) -> Result<Value> {
+    require_fs(interpreter, "writeCsv", paren)?;
+    let mut args = args.into_iter();
+    let path = string(args.next(), paren)?;
+    let rows = match args.next() {
+        Some(Value::List(rows)) => rows
+            .into_iter()
+            .map(|row| match row {
+                Value::Map(row) => Ok(row),
+                other => dump!(
+                    RuntimeError::InvalidType::<&str>(other.into(), vec![Type::Map]),
+                    paren.span.clone()
+                ),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::List]
+            ),
+            paren.span.clone()
+        ),
+    };
+    let delimiter = delimiter("writeCsv", &path, args.next(), paren)?;
+
+    let header = rows
+        .iter()
+        .flat_map(|row| row.iter().map(|(key, _)| key))
+        .collect::<IndexSet<_>>();
+
+    let io = |err: csv::Error| io_error(&path, &err, paren);
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_path(&path)
+        .map_err(io)?;
+    if !header.is_empty() {
+        writer
+            .write_record(header.iter().map(ToString::to_string))
+            .map_err(io)?;
+    }
+    for row in &rows {
+        let cells = header.iter().map(|key| match row.get(key) {
+            None | Some(Value::Null) => String::new(),
+            Some(value) => value.to_string(),
+        });
+        writer.write_record(cells).map_err(io)?;
+    }
+    writer.flush().map_err(|err| io_error(&path, &err, paren))?;
+
+    Ok(Value::Null)
+}
+
+fn string(value: Option<Value>, paren: &Token) -> Result<String> {
+    match value {
+        Some(Value::String(string)) => Ok(string),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// The byte separating the values of the file at `path`, given by `value` as a string of one
+/// ASCII character, or otherwise guessed from the extension of `path`
+fn delimiter(name: &str, path: &str, value: Option<Value>, paren: &Token) -> Result<u8> {
+    let Some(value) = value else {
+        let tsv = Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"));
+        return Ok(if tsv { b'\t' } else { b',' });
+    };
+    let delimiter = string(Some(value), paren)?;
+    match delimiter.as_bytes() {
+        &[byte] if byte.is_ascii() => Ok(byte),
+        _ => dump!(
+            RuntimeError::InvalidArgument(
+                name.to_string(),
+                format!("delimiter \"{delimiter}\", expected a single ASCII character")
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// The error for failing to read or write the file at `path`
+fn io_error(path: &str, err: &impl fmt::Display, paren: &Token) -> Report {
+    diagnostic!(
+        RuntimeError::Io::<&str> {
+            path: path.to_string(),
+            message: err.to_string(),
+        },
+        paren.span.clone()
+    )
+    .into()
+}
@@ -0,0 +1,95 @@
+//! Encoding bytes as text, and decoding them back
+//!
+//! Encoding takes a string, which is encoded as UTF-8, or a list of integers from 0 to 255 such
+//! as `s.bytes()` returns. Decoding always gives back a list of bytes, as what was encoded need
+//! not have been text.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::Result;
+use std::fmt::Write;
+
+use super::bytes;
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Token, Type, Value},
+};
+
+/// `base64Encode(data)`: `data` in standard base64, padded with `=`
+pub(super) fn base64_encode(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let data = bytes("base64Encode", args.into_iter().next(), paren)?;
+    Ok(Value::String(STANDARD.encode(data)))
+}
+
+/// `base64Decode(string)`: the bytes encoded by `string` in standard base64
+pub(super) fn base64_decode(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let encoded = string(args.into_iter().next(), paren)?;
+    match STANDARD.decode(encoded) {
+        Ok(decoded) => Ok(byte_list(decoded)),
+        Err(err) => dump!(
+            RuntimeError::InvalidArgument("base64Decode".to_string(), err.to_string()),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// `hexEncode(data)`: `data` as two lowercase hex digits per byte
+pub(super) fn hex_encode(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let data = bytes("hexEncode", args.into_iter().next(), paren)?;
+    Ok(Value::String(hex(&data)))
+}
+
+/// `hexDecode(string)`: the bytes written as pairs of hex digits, in either case, by `string`
+pub(super) fn hex_decode(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let encoded = string(args.into_iter().next(), paren)?;
+    let invalid = |message: String| {
+        dump!(
+            RuntimeError::InvalidArgument("hexDecode".to_string(), message),
+            paren.span.clone()
+        )
+    };
+    if encoded.len() % 2 == 1 {
+        return invalid(format!("\"{encoded}\" has an odd number of digits"));
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 2);
+    for (i, pair) in encoded.as_bytes().chunks(2).enumerate() {
+        let digits = String::from_utf8_lossy(pair);
+        match u8::from_str_radix(&digits, 16) {
+            Ok(byte) if pair.iter().all(u8::is_ascii_hexdigit) => decoded.push(byte),
+            _ => {
+                return invalid(format!(
+                    "\"{digits}\" at offset {} is not a hex byte",
+                    i * 2
+                ))
+            }
+        }
+    }
+    Ok(byte_list(decoded))
+}
+
+/// `bytes` as two lowercase hex digits each
+pub(super) fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{byte:02x}").expect("writing to a string cannot fail");
+    }
+    hex
+}
+
+fn byte_list(bytes: Vec<u8>) -> Value {
+    Value::List(bytes.into_iter().map(Value::from).collect())
+}
+
+fn string(value: Option<Value>, paren: &Token) -> Result<String> {
+    match value {
+        Some(Value::String(string)) => Ok(string),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
@@ -0,0 +1,99 @@
+use color_eyre::Result;
+
+use crate::{
+    ast::Quoted,
+    atium, dump,
+    error::RuntimeError,
+    interpreter::{first_error, Interpreter},
+    lexer::Cursor,
+    parser::Parser,
+    token::{Map, Token, Type, Value},
+};
+
+/// `eval(source, isolated: false)`
+///
+/// Runs `source` through the whole pipeline, returning the value of its final statement if it is
+/// an expression statement and `nil` otherwise. By default the code runs in the caller's scope,
+/// so its definitions remain visible afterwards; if `isolated` is true it instead runs in a fresh
+/// global scope, with the host's natives and prelude, that is discarded once it finishes.
+/// `isolated` may also be given as a map of options, `{"isolated": true}`.
+///
+/// Expressions deferred by the code are evaluated once it finishes, not when the caller returns.
+pub(super) fn eval(interpreter: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let source = match args.next() {
+        Some(Value::String(source)) => source,
+        Some(other) => dump!(
+            RuntimeError::InvalidType::<&str>(other.into(), vec![Type::String]),
+            paren.span.clone()
+        ),
+        None => unreachable!("arity is checked before calling"),
+    };
+    let isolated = match args.next() {
+        Some(Value::Boolean(isolated)) => isolated,
+        Some(Value::Map(options)) => isolated(&options, paren)?,
+        Some(other) => dump!(
+            RuntimeError::InvalidType::<&str>(other.into(), vec![Type::Boolean, Type::Map]),
+            paren.span.clone()
+        ),
+        None => false,
+    };
+
+    let limits = interpreter.options.parse_limits;
+    let stmts = Cursor::new(&source, Some("<eval>"))
+        .with_max_tokens(limits.map(|limits| limits.tokens))
+        .lex()
+        .and_then(|tokens| Parser::new(tokens).with_limits(limits).parse())
+        .map_err(first_error)?;
+
+    if isolated {
+        let global = atium::global_env(&interpreter.options).map_err(first_error)?;
+        let prev_env = interpreter.env.replace(global);
+        let value = interpreter.run_frame(&stmts);
+        interpreter.env.replace(prev_env);
+        value
+    } else {
+        interpreter.run_frame(&stmts)
+    }
+}
+
+/// Whether the options passed to `eval` ask for it to be isolated
+fn isolated(options: &Map, paren: &Token) -> Result<bool> {
+    let mut isolated = false;
+    for (key, value) in options.iter() {
+        match (key, value) {
+            (Value::String(key), Value::Boolean(value)) if key == "isolated" => isolated = *value,
+            (Value::String(key), value) if key == "isolated" => dump!(
+                RuntimeError::InvalidType::<&str>(value.clone().into(), vec![Type::Boolean]),
+                paren.span.clone()
+            ),
+            (key, _) => dump!(
+                RuntimeError::InvalidArgument("eval".to_string(), format!("unknown option {key}")),
+                paren.span.clone()
+            ),
+        }
+    }
+    Ok(isolated)
+}
+
+/// `evalAst(node)`
+///
+/// Evaluates a syntax tree created by `quote` in the caller's scope. A quoted expression produces
+/// its value, quoted statements behave like `eval`.
+pub(super) fn eval_ast(
+    interpreter: &Interpreter,
+    paren: &Token,
+    args: Vec<Value>,
+) -> Result<Value> {
+    match args.into_iter().next() {
+        Some(Value::Ast(quoted)) => match *quoted {
+            Quoted::Expr(expr) => interpreter.expression(&expr),
+            Quoted::Stmts(stmts) => interpreter.run(&stmts),
+        },
+        Some(other) => dump!(
+            RuntimeError::InvalidType::<&str>(other.into(), vec![Type::Ast]),
+            paren.span.clone()
+        ),
+        None => unreachable!("arity is checked before calling"),
+    }
+}
@@ -0,0 +1,32 @@
+//! Digests and checksums, with the `hash` feature
+//!
+//! Each takes a string, which is hashed as UTF-8, or a list of bytes, and returns the digest as a
+//! string of lowercase hexadecimal digits so that it can be compared with published checksums.
+//! MD5 and CRC-32 are for detecting accidental changes, not deliberate ones.
+use color_eyre::Result;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use super::{bytes, encoding::hex};
+use crate::{
+    interpreter::Interpreter,
+    token::{Token, Value},
+};
+
+/// `sha256(data)`
+pub(super) fn sha256(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let data = bytes("sha256", args.into_iter().next(), paren)?;
+    Ok(Value::String(hex(&Sha256::digest(data))))
+}
+
+/// `md5(data)`
+pub(super) fn md5(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let data = bytes("md5", args.into_iter().next(), paren)?;
+    Ok(Value::String(hex(&Md5::digest(data))))
+}
+
+/// `crc32(data)`
+pub(super) fn crc32(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let data = bytes("crc32", args.into_iter().next(), paren)?;
+    Ok(Value::String(hex(&crc32fast::hash(&data).to_be_bytes())))
+}
@@ -0,0 +1,111 @@
+use color_eyre::Result;
+
+use super::{set, string};
+use crate::{
+    interpreter::Interpreter,
+    token::{Token, Type, Value},
+};
+
+/// `help(value)`
+///
+/// Prints the signature, documentation and location of a function, class or trait. For any other value
+/// its type and methods are printed instead.
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn help(interpreter: &Interpreter, _: &Token, args: Vec<Value>) -> Result<Value> {
+    let output = &interpreter.options.output;
+    let value = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+
+    match &value {
+        Value::Function(fun) => {
+            output.writeln(&format!("fun {}({})", fun.name(), fun.decl.signature()));
+            if let Some(doc) = &fun.decl.doc {
+                output.writeln(doc);
+            }
+            output.writeln(&format!("defined at {}", fun.decl.name.span));
+        }
+        Value::NativeFn(native) => {
+            match native.doc {
+                Some(doc) => output.writeln(doc),
+                None => output.writeln(&format!("{}(...)", native.name)),
+            }
+            output.writeln("native function");
+        }
+        Value::Class(class) => {
+            output.writeln(&format!("class {}", class.name()));
+            if let Some(doc) = &class.doc {
+                output.writeln(doc);
+            }
+            let methods = class.method_names();
+            if !methods.is_empty() {
+                output.writeln(&format!("methods: {}", methods.join(", ")));
+            }
+            let statics = class.static_names();
+            if !statics.is_empty() {
+                output.writeln(&format!("static methods: {}", statics.join(", ")));
+            }
+            output.writeln(&format!("defined at {}", class.name.span));
+        }
+        Value::Trait(r#trait) => {
+            output.writeln(&format!("trait {}", r#trait.name()));
+            if let Some(doc) = &r#trait.doc {
+                output.writeln(doc);
+            }
+            let methods = r#trait.methods.iter().map(ToString::to_string);
+            output.writeln(&format!(
+                "requires: {}",
+                methods.collect::<Vec<_>>().join(", ")
+            ));
+            output.writeln(&format!("defined at {}", r#trait.name.span));
+        }
+        value => {
+            output.writeln(&format!("value of type {}", Type::from(value.clone())));
+            let methods = methods(value);
+            if !methods.is_empty() {
+                output.writeln(&format!("methods: {}", methods.join(", ")));
+            }
+        }
+    }
+    Ok(Value::Null)
+}
+
+/// `typeof(value)`
+///
+/// The name of the type of `value`, as errors refer to it. Use `is` to check the class of an
+/// instance.
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn type_of(_: &Interpreter, _: &Token, args: Vec<Value>) -> Result<Value> {
+    let value = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+
+    Ok(Value::String(Type::from(value).to_string()))
+}
+
+/// `dir(value)`
+///
+/// Lists the names of the methods that can be called on `value`
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn dir(_: &Interpreter, _: &Token, args: Vec<Value>) -> Result<Value> {
+    let value = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+
+    Ok(Value::List(
+        methods(&value).into_iter().map(Value::String).collect(),
+    ))
+}
+
+fn methods(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(_) => string::METHODS.map(str::to_string).to_vec(),
+        Value::Set(_) => set::METHODS.map(str::to_string).to_vec(),
+        Value::Instance(instance) => instance.class.method_names(),
+        Value::Class(class) => class.static_names(),
+        _ => vec![],
+    }
+}
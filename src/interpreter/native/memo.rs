@@ -0,0 +1,86 @@
+use color_eyre::Result;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use super::NativeFn;
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Map, Token, Value},
+};
+
+/// The cache of a memoized function, shared with `cacheStats`
+#[derive(Default)]
+struct Memo {
+    cache: RefCell<HashMap<Vec<Value>, Value>>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+}
+
+/// `memoize(fn)`
+///
+/// Wraps `fn` so that calling it with arguments it has already been called with returns the
+/// previous result instead of calling `fn` again. Calls that error are not cached.
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn memoize(_: &Interpreter, _: &Token, args: Vec<Value>) -> Result<Value> {
+    let fun = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+    let name = match &fun {
+        Value::Function(fun) => fun.name(),
+        Value::NativeFn(native) => native.name.clone(),
+        // calling the wrapper reports that the value is not callable
+        other => other.to_string(),
+    };
+
+    let memo = Rc::new(Memo::default());
+    let state = Rc::clone(&memo);
+    // the arity of the wrapped function is checked when it is called
+    let wrapper = NativeFn::new(&name, 0..=usize::MAX, move |interpreter, paren, args| {
+        if let Some(value) = memo.cache.borrow().get(&args) {
+            memo.hits.set(memo.hits.get() + 1);
+            return Ok(value.clone());
+        }
+
+        memo.misses.set(memo.misses.get() + 1);
+        let value = interpreter.call(fun.clone(), paren, args.clone())?;
+        memo.cache.borrow_mut().insert(args, value.clone());
+        Ok(value)
+    });
+
+    Ok(Value::NativeFn(Rc::new(wrapper.with_state(state))))
+}
+
+/// `cacheStats(fn)`
+///
+/// Describes how effective the cache of a function created by `memoize` has been, as a map of the
+/// number of `hits`, `misses` and cached `entries`
+pub(super) fn cache_stats(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let fun = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+    let Some(memo) = (match &fun {
+        Value::NativeFn(native) => native.state::<Memo>(),
+        _ => None,
+    }) else {
+        dump!(
+            RuntimeError::NotMemoized::<&str>(fun.to_string()),
+            paren.span.clone()
+        )
+    };
+
+    let count =
+        |name: &str, count: usize| (Value::from(name.to_string()), Value::Integer(count as i128));
+    let entries = memo.cache.borrow().len();
+    Ok(Value::Map(Map::from_iter([
+        count("hits", memo.hits.get()),
+        count("misses", memo.misses.get()),
+        count("entries", entries),
+    ])))
+}
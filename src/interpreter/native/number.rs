@@ -0,0 +1,171 @@
+use color_eyre::Result;
+use ordered_float::OrderedFloat;
+
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Token, Type, Value},
+};
+
+/// `formatNumber(number, digits)`
+///
+/// Formats `number` with exactly `digits` digits after the decimal point, rounding if needed
+pub(super) fn format_number(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let number = args.next().expect("arity is checked before calling");
+    let digits = match args.next() {
+        Some(Value::Integer(digits)) => match usize::try_from(digits) {
+            Ok(digits) => digits,
+            Err(_) => dump!(
+                RuntimeError::InvalidArgument(
+                    String::from("formatNumber"),
+                    format!("{digits} digits, expected at least 0")
+                ),
+                paren.span.clone()
+            ),
+        },
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::Integer]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    let formatted = match number {
+        Value::Integer(int) if digits == 0 => int.to_string(),
+        Value::Integer(int) => format!("{int}.{}", "0".repeat(digits)),
+        Value::Float(float) => format!("{:.digits$}", float.0),
+        // halves round to even, as `format!` rounds the exact value of floats
+        #[cfg(feature = "decimal")]
+        Value::Decimal(decimal) => {
+            let dp = u32::try_from(digits).unwrap_or(u32::MAX);
+            let rounded = decimal
+                .round_dp_with_strategy(dp, rust_decimal::RoundingStrategy::MidpointNearestEven);
+            format!("{rounded:.digits$}")
+        }
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(other.into(), vec![Type::Integer, Type::Float]),
+            paren.span.clone()
+        ),
+    };
+    Ok(Value::String(formatted))
+}
+
+/// `parseNumber(string)`
+///
+/// Parses `string` as a number literal would be lexed, optionally preceded by a minus sign. The
+/// result is a float if the string contains a decimal point and an integer otherwise.
+pub(super) fn parse_number(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let string = match args.into_iter().next() {
+        Some(Value::String(string)) => string,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    let trimmed = string.trim();
+    let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let is_number = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+
+    let number = if !is_number {
+        None
+    } else if digits.contains('.') {
+        trimmed
+            .parse()
+            .ok()
+            .map(|float| Value::Float(OrderedFloat(float)))
+    } else {
+        trimmed.parse().ok().map(Value::Integer)
+    };
+
+    match number {
+        Some(number) => Ok(number),
+        None => dump!(
+            RuntimeError::InvalidArgument(
+                String::from("parseNumber"),
+                format!("\"{string}\" is not a number")
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// `range(start?, end)`
+///
+/// The integers from `start`, or 0 if it is omitted, up to but not including `end`. Only the
+/// bounds are stored, the integers being produced as the range is iterated over.
+pub(super) fn range(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut bounds = vec![];
+    for arg in args {
+        match arg {
+            Value::Integer(bound) => bounds.push(bound),
+            other => dump!(
+                RuntimeError::InvalidType::<&str>(other.into(), vec![Type::Integer]),
+                paren.span.clone()
+            ),
+        }
+    }
+
+    let (start, end) = match bounds[..] {
+        [end] => (0, end),
+        [start, end] => (start, end),
+        _ => unreachable!("arity is checked before calling"),
+    };
+    Ok(Value::Range(start, end))
+}
+
+/// `decimal(value)`
+///
+/// Converts an integer, float or string to a decimal. A float converts to the decimal with the
+/// digits it is displayed with, so `decimal(0.1)` is exactly `0.1d`.
+#[cfg(feature = "decimal")]
+pub(super) fn decimal(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    use rust_decimal::Decimal;
+
+    let value = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+    let parse = |string: &str| {
+        string
+            .trim()
+            .parse::<Decimal>()
+            .or_else(|_| Decimal::from_scientific(string.trim()))
+            .ok()
+    };
+
+    let decimal = match &value {
+        Value::Integer(int) => Decimal::try_from_i128_with_scale(*int, 0).ok(),
+        Value::Float(float) if float.is_finite() => parse(&value.to_string()),
+        Value::String(string) => parse(string),
+        Value::Decimal(decimal) => Some(*decimal),
+        Value::Float(_) => None,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.clone().into(),
+                vec![Type::Integer, Type::Float, Type::String, Type::Decimal]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    match decimal {
+        Some(decimal) => Ok(Value::Decimal(decimal)),
+        None => dump!(
+            RuntimeError::InvalidArgument(
+                String::from("decimal"),
+                format!("{} cannot be represented as a decimal", value.nested())
+            ),
+            paren.span.clone()
+        ),
+    }
+}
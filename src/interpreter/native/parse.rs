@@ -0,0 +1,143 @@
+//! Parsing data formats into values, through a bridge from serde that any format can share
+//!
+//! Objects and tables become maps, keeping the order of their keys, and arrays become lists.
+//! Numbers are integers unless they have a fractional part or exponent, and null is `nil`.
+use color_eyre::Result;
+use ordered_float::OrderedFloat;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Map, Token, Type, Value},
+};
+
+/// `parseJson(string)`
+pub(super) fn parse_json(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let source = source(args, paren)?;
+    parsed("parseJson", serde_json::from_str(&source), paren)
+}
+
+/// `parseToml(string)`
+///
+/// Dates and times are strings, written as they are in TOML.
+#[cfg(feature = "toml")]
+pub(super) fn parse_toml(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let source = source(args, paren)?;
+    parsed("parseToml", toml::from_str(&source), paren)
+}
+
+/// `parseYaml(string)`
+#[cfg(feature = "yaml")]
+pub(super) fn parse_yaml(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let source = source(args, paren)?;
+    parsed("parseYaml", serde_yaml::from_str(&source), paren)
+}
+
+fn source(args: Vec<Value>, paren: &Token) -> Result<String> {
+    match args.into_iter().next() {
+        Some(Value::String(source)) => Ok(source),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// The value parsed by the native `name`, or an error describing why it could not be parsed
+fn parsed<E: fmt::Display>(name: &str, result: Result<Plain, E>, paren: &Token) -> Result<Value> {
+    match result {
+        Ok(Plain(value)) => Ok(value),
+        Err(err) => dump!(
+            RuntimeError::InvalidArgument(name.to_string(), err.to_string()),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// A value deserialised from the data model of serde, rather than from the representation of
+/// [`Value`] that its own `Deserialize` implementation expects
+struct Plain(Value);
+
+/// The key that the `toml` crate stores dates and times under, in a table of their own
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+impl<'de> Deserialize<'de> for Plain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PlainVisitor)
+    }
+}
+
+struct PlainVisitor;
+
+impl<'de> Visitor<'de> for PlainVisitor {
+    type Value = Plain;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a boolean, number, string, null, array or object")
+    }
+
+    fn visit_bool<E: de::Error>(self, bool: bool) -> Result<Plain, E> {
+        Ok(Plain(Value::Boolean(bool)))
+    }
+
+    fn visit_i64<E: de::Error>(self, int: i64) -> Result<Plain, E> {
+        Ok(Plain(Value::from(int)))
+    }
+
+    fn visit_i128<E: de::Error>(self, int: i128) -> Result<Plain, E> {
+        Ok(Plain(Value::Integer(int)))
+    }
+
+    fn visit_u64<E: de::Error>(self, int: u64) -> Result<Plain, E> {
+        Ok(Plain(Value::from(int)))
+    }
+
+    fn visit_f64<E: de::Error>(self, float: f64) -> Result<Plain, E> {
+        Ok(Plain(Value::Float(OrderedFloat(float))))
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Plain, E> {
+        Ok(Plain(Value::String(string.to_string())))
+    }
+
+    fn visit_string<E: de::Error>(self, string: String) -> Result<Plain, E> {
+        Ok(Plain(Value::String(string)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Plain, E> {
+        Ok(Plain(Value::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Plain, E> {
+        Ok(Plain(Value::Null))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Plain, D::Error> {
+        Plain::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Plain, A::Error> {
+        let mut list = vec![];
+        while let Some(Plain(element)) = seq.next_element()? {
+            list.push(element);
+        }
+        Ok(Plain(Value::List(list)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Plain, A::Error> {
+        let mut map = Map::new();
+        while let Some((Plain(key), Plain(value))) = access.next_entry()? {
+            if key == Value::String(TOML_DATETIME.to_string()) {
+                return Ok(Plain(value));
+            }
+            map.insert(key, value);
+        }
+        Ok(Plain(Value::Map(map)))
+    }
+}
@@ -0,0 +1,88 @@
+//! Finding files and taking paths apart
+//!
+//! Only `glob` looks at the filesystem, and so needs the fs capability. The others work on paths
+//! as strings, whether or not anything exists at them.
+use color_eyre::Result;
+use std::path::{Path, PathBuf};
+
+use super::require_fs;
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{Token, Type, Value},
+};
+
+/// `glob(pattern)`
+///
+/// The paths matching `pattern`, in alphabetical order. `*` matches within a part of a path,
+/// `**` matches any number of directories, and `?` and `[abc]` match a single character.
+pub(super) fn glob(interpreter: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    require_fs(interpreter, "glob", paren)?;
+    let pattern = string(args.into_iter().next(), paren)?;
+
+    let paths = match glob::glob(&pattern) {
+        Ok(paths) => paths,
+        Err(err) => dump!(
+            RuntimeError::InvalidArgument("glob".to_string(), err.to_string()),
+            paren.span.clone()
+        ),
+    };
+    let mut matches = vec![];
+    for path in paths {
+        match path {
+            Ok(path) => matches.push(Value::String(path.to_string_lossy().into_owned())),
+            Err(err) => dump!(
+                RuntimeError::Io::<&str> {
+                    path: err.path().to_string_lossy().into_owned(),
+                    message: err.error().to_string(),
+                },
+                paren.span.clone()
+            ),
+        }
+    }
+    Ok(Value::List(matches))
+}
+
+/// `pathJoin(parts...)`
+///
+/// The parts joined with the separator of the platform. A part that is absolute replaces
+/// everything before it.
+pub(super) fn path_join(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut path = PathBuf::new();
+    for part in args {
+        path.push(string(Some(part), paren)?);
+    }
+    Ok(Value::String(path.to_string_lossy().into_owned()))
+}
+
+/// `basename(path)`: the last part of `path`, or `nil` if it ends in `..` or is a root
+pub(super) fn basename(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let path = string(args.into_iter().next(), paren)?;
+    Ok(Path::new(&path).file_name().map_or(Value::Null, |name| {
+        Value::String(name.to_string_lossy().into_owned())
+    }))
+}
+
+/// `extension(path)`: the extension of `path` without its dot, or `nil` if it has none
+pub(super) fn extension(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let path = string(args.into_iter().next(), paren)?;
+    Ok(Path::new(&path)
+        .extension()
+        .map_or(Value::Null, |extension| {
+            Value::String(extension.to_string_lossy().into_owned())
+        }))
+}
+
+fn string(value: Option<Value>, paren: &Token) -> Result<String> {
+    match value {
+        Some(Value::String(string)) => Ok(string),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
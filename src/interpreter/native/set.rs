@@ -0,0 +1,45 @@
+//! Sets are values like any other, so their methods return new sets rather than changing the set
+//! that they are called on: `s = s.add(4)` rather than `s.add(4)`.
+use color_eyre::Result;
+
+use super::{Interpreter, NativeFn};
+use crate::{
+    interpreter::iterate,
+    token::{Set, Token, Value},
+};
+
+/// `set(iterable)`: a set of the elements of `iterable`, in the order they are iterated over
+pub(super) fn set(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let iterable = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+    let elements = iterate::elements(iterable, paren.span.clone())?;
+
+    Ok(Value::Set(elements.collect()))
+}
+
+/// The names of the methods of sets
+pub(in crate::interpreter) const METHODS: [&str; 2] = ["add", "contains"];
+
+/// Looks up a method of `set`, returning it bound to `set`
+pub(in crate::interpreter) fn method(set: &Set, name: &str) -> Option<NativeFn> {
+    let method: fn(&Set, Value) -> Value = match name {
+        "add" => |set, value| {
+            let mut set = set.clone();
+            set.insert(value);
+            Value::Set(set)
+        },
+        "contains" => |set, value| Value::Boolean(set.contains(&value)),
+        _ => return None,
+    };
+
+    let set = set.clone();
+    Some(NativeFn::new(name, 1..=1, move |_, _, args| {
+        let value = args
+            .into_iter()
+            .next()
+            .expect("arity is checked before calling");
+        Ok(method(&set, value))
+    }))
+}
@@ -0,0 +1,177 @@
+//! Strings are sequences of graphemes: the characters that a reader perceives. `len("é")` is
+//! therefore 1 regardless of whether the `é` is a single code point or a combining sequence, and
+//! indexing can never split a character in two. The underlying bytes and chars are available
+//! through `s.bytes()` and `s.chars()`.
+//!
+//! Strings also have the methods `len`, `upper`, `lower`, `trim`, `split`, `contains` and
+//! `replace`, which return new strings rather than changing the one they are called on.
+use color_eyre::Result;
+use std::ops::RangeInclusive;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Interpreter, NativeFn};
+use crate::{
+    dump,
+    error::RuntimeError,
+    token::{Token, Type, Value},
+};
+
+/// `len(value)`: the number of graphemes in a string, or elements in a list, tuple, range, map or
+/// set
+pub(super) fn len(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let len = match args.into_iter().next() {
+        Some(Value::String(string)) => len_of(&string),
+        Some(Value::List(list) | Value::Tuple(list)) => list.len(),
+        Some(Value::Range(start, end)) if end <= start => return Ok(Value::Integer(0)),
+        Some(Value::Range(start, end)) => {
+            let Some(len) = end.checked_sub(start) else {
+                dump!(RuntimeError::IntegerOverflow("len"), paren.span.clone())
+            };
+            return Ok(Value::Integer(len));
+        }
+        Some(Value::Map(map)) => map.len(),
+        Some(Value::Set(set)) => set.len(),
+        value => dump!(
+            RuntimeError::InvalidType::<&str>(
+                value.unwrap_or(Value::Null).into(),
+                vec![
+                    Type::String,
+                    Type::List,
+                    Type::Tuple,
+                    Type::Range,
+                    Type::Map,
+                    Type::Set
+                ]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    Ok(Value::Integer(len as i128))
+}
+
+/// `str(value)`: `value` as `print` would display it
+#[allow(clippy::unnecessary_wraps)] // all natives share a signature
+pub(super) fn str(_: &Interpreter, _: &Token, args: Vec<Value>) -> Result<Value> {
+    let value = args
+        .into_iter()
+        .next()
+        .expect("arity is checked before calling");
+    Ok(Value::String(value.to_string()))
+}
+
+/// The number of graphemes in `string`
+pub(in crate::interpreter) fn len_of(string: &str) -> usize {
+    string.graphemes(true).count()
+}
+
+/// The grapheme at `index` of `string`, if there is one
+pub(in crate::interpreter) fn grapheme(string: &str, index: i128) -> Option<Value> {
+    let index = usize::try_from(index).ok()?;
+    string
+        .graphemes(true)
+        .nth(index)
+        .map(|grapheme| Value::String(grapheme.to_string()))
+}
+
+/// The names of the methods of strings
+pub(in crate::interpreter) const METHODS: [&str; 10] = [
+    "bytes",
+    "chars",
+    "contains",
+    "graphemes",
+    "len",
+    "lower",
+    "replace",
+    "split",
+    "trim",
+    "upper",
+];
+
+type Method = fn(&str, &Token, Vec<Value>) -> Result<Value>;
+
+/// Looks up a method of `string`, returning it bound to `string`
+pub(in crate::interpreter) fn method(string: &str, name: &str) -> Option<NativeFn> {
+    let (arity, method): (RangeInclusive<usize>, Method) = match name {
+        "bytes" => (0..=0, |string, _, _| {
+            Ok(Value::List(string.bytes().map(Value::from).collect()))
+        }),
+        "chars" => (0..=0, |string, _, _| {
+            Ok(strings(string.chars().map(|c| c.to_string())))
+        }),
+        "graphemes" => (0..=0, |string, _, _| {
+            Ok(strings(string.graphemes(true).map(str::to_string)))
+        }),
+        "len" => (0..=0, |string, _, _| {
+            Ok(Value::Integer(len_of(string) as i128))
+        }),
+        "upper" => (0..=0, |string, _, _| {
+            Ok(Value::String(string.to_uppercase()))
+        }),
+        "lower" => (0..=0, |string, _, _| {
+            Ok(Value::String(string.to_lowercase()))
+        }),
+        "trim" => (0..=0, |string, _, _| {
+            Ok(Value::String(string.trim().to_string()))
+        }),
+        "split" => (0..=1, split),
+        "contains" => (1..=1, |string, paren, args| {
+            let part = argument(args.into_iter().next(), paren)?;
+            Ok(Value::Boolean(string.contains(&part)))
+        }),
+        "replace" => (2..=2, replace),
+        _ => return None,
+    };
+
+    let string = string.to_string();
+    Some(NativeFn::new(name, arity, move |_, paren, args| {
+        method(&string, paren, args)
+    }))
+}
+
+/// `s.split(separator?)`: the parts of `s` between each `separator`, or between runs of
+/// whitespace if it is not given
+fn split(string: &str, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let Some(separator) = args.into_iter().next() else {
+        return Ok(strings(string.split_whitespace().map(str::to_string)));
+    };
+    let separator = pattern("split", Some(separator), paren)?;
+    Ok(strings(string.split(&separator).map(str::to_string)))
+}
+
+/// `s.replace(from, to)`: `s` with every occurrence of `from` replaced by `to`
+fn replace(string: &str, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let from = pattern("replace", args.next(), paren)?;
+    let to = argument(args.next(), paren)?;
+    Ok(Value::String(string.replace(&from, &to)))
+}
+
+fn strings(strings: impl Iterator<Item = String>) -> Value {
+    Value::List(strings.map(Value::String).collect())
+}
+
+fn argument(value: Option<Value>, paren: &Token) -> Result<String> {
+    match value {
+        Some(Value::String(string)) => Ok(string),
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    }
+}
+
+/// A string to search for, which cannot be empty as it would be found between every character
+fn pattern(name: &str, value: Option<Value>, paren: &Token) -> Result<String> {
+    let pattern = argument(value, paren)?;
+    if pattern.is_empty() {
+        dump!(
+            RuntimeError::InvalidArgument(name.to_string(), "an empty string to search for"),
+            paren.span.clone()
+        );
+    }
+    Ok(pattern)
+}
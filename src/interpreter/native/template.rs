@@ -0,0 +1,283 @@
+//! Templates, which `render` fills in with values
+//!
+//! Text is copied as it is, apart from tags in double braces:
+//!
+//! - `{{name}}` is replaced by the value of `name`, displayed as `print` would. Properties of it
+//!   are reached with dots, as in `{{user.name}}`, and are read as in code, so getters are called.
+//! - `{{#for item in items}} ... {{/for}}` repeats its body for each element of `items`, with
+//!   `item` bound to the element.
+//! - `{{#if name}} ... {{#else}} ... {{/if}}` renders its first body if `name` is truthy, and its
+//!   `else` body, if there is one, otherwise. `false`, `nil` and empty strings and collections
+//!   are falsy, and everything else is truthy.
+//!
+//! Names are looked up in the loops enclosing them, innermost first, and then in the variables
+//! passed to `render`. A name that is not found is an error, rather than rendering as nothing.
+use color_eyre::{Report, Result};
+
+use crate::{
+    diagnostic, dump,
+    error::{RuntimeError, Span},
+    interpreter::{iterate, Interpreter},
+    token::{Token, TokenKind, Type, Value},
+};
+
+/// `render(template, vars)`
+///
+/// Fills in `template` with the fields of the instance, or entries of the map, `vars`
+pub(super) fn render(interpreter: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let template = match args.next() {
+        Some(Value::String(template)) => template,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    };
+    let vars = match args.next() {
+        Some(vars @ (Value::Map(_) | Value::Instance(_))) => vars,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::Map, Type::Instance]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    let mut tags = Tags {
+        rest: &template,
+        span: &paren.span,
+    };
+    let (nodes, end) = tags.nodes()?;
+    if let Some(end) = end {
+        dump!(
+            RuntimeError::InvalidTemplate(format!("{{{{{end}}}}} has no opening tag")),
+            paren.span.clone()
+        );
+    }
+
+    let mut out = String::new();
+    Renderer {
+        interpreter,
+        vars,
+        loops: vec![],
+        span: &paren.span,
+    }
+    .render(&nodes, &mut out)?;
+    Ok(Value::String(out))
+}
+
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    For {
+        name: &'a str,
+        iterable: &'a str,
+        body: Vec<Self>,
+    },
+    If {
+        condition: &'a str,
+        then: Vec<Self>,
+        otherwise: Vec<Self>,
+    },
+}
+
+/// The part of a template left to be parsed
+struct Tags<'a, 's> {
+    rest: &'a str,
+    /// where errors are reported, as templates are not source code
+    span: &'s Span,
+}
+
+impl<'a> Tags<'a, '_> {
+    /// The nodes up to the closing tag that ends them, which is returned too, or the end of the
+    /// template
+    fn nodes(&mut self) -> Result<(Vec<Node<'a>>, Option<&'a str>)> {
+        let mut nodes = vec![];
+        loop {
+            let Some(start) = self.rest.find("{{") else {
+                if !self.rest.is_empty() {
+                    nodes.push(Node::Text(self.rest));
+                }
+                return Ok((nodes, None));
+            };
+            if start > 0 {
+                nodes.push(Node::Text(&self.rest[..start]));
+            }
+            let Some(len) = self.rest[start..].find("}}") else {
+                return Err(self.error("{{ is never closed with }}"));
+            };
+            let tag = self.rest[start + 2..start + len].trim();
+            self.rest = &self.rest[start + len + 2..];
+
+            if tag.starts_with('/') || tag == "#else" {
+                return Ok((nodes, Some(tag)));
+            }
+            nodes.push(match tag.strip_prefix('#') {
+                Some(block) => self.block(block)?,
+                None => Node::Var(tag),
+            });
+        }
+    }
+
+    /// The block opened by `{{#block}}`, up to and including its closing tag
+    fn block(&mut self, block: &'a str) -> Result<Node<'a>> {
+        let (keyword, rest) = block.split_once(' ').unwrap_or((block, ""));
+        match keyword {
+            "for" => {
+                let Some((name, iterable)) = rest.split_once(" in ") else {
+                    return Err(self.error("expected {{#for name in iterable}}"));
+                };
+                let body = self.body("for", false)?.0;
+                Ok(Node::For {
+                    name: name.trim(),
+                    iterable: iterable.trim(),
+                    body,
+                })
+            }
+            "if" => {
+                let (then, otherwise) = self.body("if", true)?;
+                Ok(Node::If {
+                    condition: rest.trim(),
+                    then,
+                    otherwise,
+                })
+            }
+            _ => Err(self.error(&format!("unknown block {{{{#{keyword}}}}}"))),
+        }
+    }
+
+    /// The nodes of a block up to `{{/keyword}}`, and those after `{{#else}}` if it can have one
+    fn body(&mut self, keyword: &str, can_else: bool) -> Result<(Vec<Node<'a>>, Vec<Node<'a>>)> {
+        let close = format!("/{keyword}");
+        let (body, end) = self.nodes()?;
+        match end {
+            Some(end) if end == close => Ok((body, vec![])),
+            Some("#else") if can_else => match self.nodes()? {
+                (otherwise, Some(end)) if end == close => Ok((body, otherwise)),
+                _ => Err(self.error(&format!("{{{{#{keyword}}}}} is never closed"))),
+            },
+            _ => Err(self.error(&format!("{{{{#{keyword}}}}} is never closed"))),
+        }
+    }
+
+    fn error(&self, message: &str) -> Report {
+        diagnostic!(
+            RuntimeError::InvalidTemplate(message.to_string()),
+            self.span.clone()
+        )
+        .into()
+    }
+}
+
+struct Renderer<'s> {
+    interpreter: &'s Interpreter,
+    vars: Value,
+    /// the element that each enclosing loop is on, innermost last
+    loops: Vec<(String, Value)>,
+    span: &'s Span,
+}
+
+impl Renderer<'_> {
+    fn render(&mut self, nodes: &[Node<'_>], out: &mut String) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Var(path) => out.push_str(&self.lookup(path)?.to_string()),
+                Node::For {
+                    name,
+                    iterable,
+                    body,
+                } => {
+                    let elements = iterate::elements(self.lookup(iterable)?, self.span.clone())?;
+                    for element in elements {
+                        self.loops.push(((*name).to_string(), element));
+                        let rendered = self.render(body, out);
+                        self.loops.pop();
+                        rendered?;
+                    }
+                }
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    if truthy(&self.lookup(condition)?) {
+                        self.render(then, out)?;
+                    } else {
+                        self.render(otherwise, out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The value that the dotted `path` names
+    fn lookup(&self, path: &str) -> Result<Value> {
+        let mut names = path.split('.').map(str::trim);
+        let first = names.next().unwrap_or_default();
+        let root = self
+            .loops
+            .iter()
+            .rev()
+            .find(|(name, _)| name == first)
+            .map(|(_, element)| element.clone());
+        let mut value = match root {
+            Some(root) => Some(root),
+            None => self.property(&self.vars, first)?,
+        };
+        for name in names {
+            value = match value {
+                Some(value) => self.property(&value, name)?,
+                None => None,
+            };
+        }
+        match value {
+            Some(value) => Ok(value),
+            None => dump!(
+                RuntimeError::InvalidTemplate(format!("{path} is not defined")),
+                self.span.clone()
+            ),
+        }
+    }
+
+    /// The property of an instance, or entry of a map, called `name`
+    ///
+    /// Properties are read as `instance.name` would read them, so getters are called.
+    fn property(&self, value: &Value, name: &str) -> Result<Option<Value>> {
+        match value {
+            Value::Map(map) => Ok(map.get(&Value::String(name.to_string())).cloned()),
+            Value::Instance(instance)
+                if instance.get(name).is_some() || instance.getter(name).is_some() =>
+            {
+                let name = Token::new(
+                    TokenKind::Identifier,
+                    None,
+                    Span {
+                        lex: name.to_string(),
+                        ..self.span.clone()
+                    },
+                );
+                self.interpreter.property(value.clone(), &name).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Boolean(bool) => *bool,
+        Value::Null => false,
+        Value::String(string) => !string.is_empty(),
+        Value::List(list) | Value::Tuple(list) => !list.is_empty(),
+        Value::Range(start, end) => start < end,
+        Value::Map(map) => !map.is_empty(),
+        Value::Set(set) => !set.is_empty(),
+        _ => true,
+    }
+}
@@ -0,0 +1,83 @@
+//! Natives for the quantities of the `units` feature
+use color_eyre::Result;
+
+use crate::{
+    dump,
+    error::RuntimeError,
+    interpreter::Interpreter,
+    token::{quantity::Quantity, Token, Type, Value},
+};
+
+/// `quantity(number, unit)`
+///
+/// `number` measured in `unit`, which may be compound, as in `"m/s^2"`
+#[allow(clippy::cast_precision_loss)]
+pub(super) fn quantity(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let number = match args.next() {
+        Some(Value::Integer(int)) => int as f64,
+        Some(Value::Float(float)) => float.0,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::Integer, Type::Float]
+            ),
+            paren.span.clone()
+        ),
+    };
+    let unit = unit("quantity", paren, args.next())?;
+
+    Ok(Quantity::scalar(number).times(unit).into_value())
+}
+
+/// `convert(quantity, unit)`
+///
+/// The number of `unit` in `quantity`, which must be of the same dimension
+pub(super) fn convert(_: &Interpreter, paren: &Token, args: Vec<Value>) -> Result<Value> {
+    let mut args = args.into_iter();
+    let quantity = match args.next() {
+        Some(Value::Quantity(quantity)) => quantity,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::Quantity]
+            ),
+            paren.span.clone()
+        ),
+    };
+    let unit = unit("convert", paren, args.next())?;
+
+    if quantity.dimension != unit.dimension {
+        dump!(
+            RuntimeError::DimensionMismatch(
+                String::from("convert"),
+                quantity.dimension.to_string(),
+                unit.dimension.to_string()
+            ),
+            paren.span.clone()
+        );
+    }
+    Ok(Value::Float(quantity.per(unit).magnitude))
+}
+
+/// The unit named by the string argument `unit` of the native `name`
+fn unit(name: &str, paren: &Token, unit: Option<Value>) -> Result<Quantity> {
+    let unit = match unit {
+        Some(Value::String(unit)) => unit,
+        other => dump!(
+            RuntimeError::InvalidType::<&str>(
+                other.unwrap_or(Value::Null).into(),
+                vec![Type::String]
+            ),
+            paren.span.clone()
+        ),
+    };
+
+    match Quantity::parse(&unit) {
+        Some(quantity) => Ok(quantity),
+        None => dump!(
+            RuntimeError::InvalidArgument(name.to_string(), format!("unknown unit \"{unit}\"")),
+            paren.span.clone()
+        ),
+    }
+}
@@ -0,0 +1,387 @@
+//! Evaluation of unary and binary operators
+//!
+//! Integers and floats can be mixed freely in arithmetic and comparisons, the integer being
+//! converted to a float. Integer arithmetic is checked, so overflowing or dividing by zero is an
+//! error rather than a panic, whereas float arithmetic and comparisons follow IEEE 754, so `NaN`
+//! is not equal to itself.
+//!
+//! `+` also concatenates strings. Adding a string to any other value is an error unless
+//! [`Options::coerce_strings`](crate::atium::Options::coerce_strings) is set, in which case the
+//! other value is converted to a string.
+//!
+//! Any two values can be tested for equality, values of different types never being equal other
+//! than numbers. Only numbers and strings, which compare lexicographically, can be ordered.
+//!
+//! With the `decimal` feature, decimals mix with integers exactly, but never with floats, which
+//! must be converted with `decimal` first.
+//!
+//! With the `units` feature, quantities can be multiplied and divided by each other and by
+//! numbers, but only added to, subtracted from and compared with quantities of the same
+//! dimension. A result whose units cancel out is a float.
+//!
+//! Sets combine with `|` for their union, `&` for their intersection and `-` for their
+//! difference.
+//!
+//! `value is Type` tests whether `value` is an instance of the class `Type`, or of a class that
+//! implements the trait `Type`.
+use color_eyre::Result;
+use ordered_float::OrderedFloat;
+use std::{cmp::Ordering, rc::Rc};
+
+#[cfg(feature = "units")]
+use crate::token::quantity::Quantity;
+
+use crate::{
+    diagnostic, dump,
+    error::{Diagnostic, RuntimeError},
+    token::{Token, TokenKind, Type, Value},
+};
+
+pub(super) fn unary(op: &Token, value: Value) -> Result<Value> {
+    match op.kind {
+        TokenKind::Minus => match value {
+            Value::Integer(a) => a.checked_neg().map(Value::Integer).ok_or_else(|| {
+                diagnostic!(RuntimeError::IntegerOverflow(op.lex()), op.span.clone()).into()
+            }),
+            Value::Float(a) => Ok(Value::Float(-a)),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(a) => Ok(Value::Decimal(-a)),
+            #[cfg(feature = "units")]
+            Value::Quantity(a) => Ok(Value::Quantity(-a)),
+            _ => dump!(
+                RuntimeError::InvalidOperand(
+                    op.lex(),
+                    value.into(),
+                    vec![Type::Integer, Type::Float]
+                ),
+                op.span.clone()
+            ),
+        },
+        TokenKind::Bang => truth(op, value).map(|a| Value::Boolean(!a)),
+        _ => dump!(
+            RuntimeError::InvalidOperator::<&str>(op.lex(), vec!["-", "!"]),
+            op.span.clone()
+        ),
+    }
+}
+
+/// The value of an operand of `op` that must be a boolean, such as those of `!` and `and`
+///
+/// There is no truthiness: `nil`, like every other value that is not a boolean, is an error
+/// rather than being taken as false.
+pub(super) fn truth(op: &Token, value: Value) -> Result<bool> {
+    match value {
+        Value::Boolean(a) => Ok(a),
+        _ => dump!(
+            RuntimeError::InvalidOperand(op.lex(), value.into(), vec![Type::Boolean]),
+            op.span.clone()
+        ),
+    }
+}
+
+pub(super) fn binary(op: &Token, left: Value, right: Value) -> Result<Value> {
+    match op.kind {
+        TokenKind::Plus | TokenKind::Minus | TokenKind::Star | TokenKind::Slash => {
+            arithmetic(op, left, right)
+        }
+        TokenKind::EqualEqual => Ok(Value::Boolean(equal(&left, &right))),
+        TokenKind::BangEqual => Ok(Value::Boolean(!equal(&left, &right))),
+        TokenKind::Greater | TokenKind::GreaterEqual | TokenKind::Less | TokenKind::LessEqual => {
+            compare(op, left, right)
+        }
+        TokenKind::Is => is(op, &left, right),
+        TokenKind::Bar | TokenKind::Ampersand => sets(op, left, right),
+        _ => dump!(
+            RuntimeError::InvalidOperator::<&str>(
+                op.lex(),
+                vec!["+", "/", "-", "*", "|", "&", "==", "!=", ">", ">=", "<", "<=", "is",]
+            ),
+            op.span.clone()
+        ),
+    }
+}
+
+/// Whether `value` is an instance of the class `ty`, or of a class implementing the trait `ty`
+fn is(op: &Token, value: &Value, ty: Value) -> Result<Value> {
+    let class = match value {
+        Value::Instance(instance) => Some(&instance.class),
+        _ => None,
+    };
+    match ty {
+        Value::Class(ty) => Ok(Value::Boolean(
+            class.is_some_and(|class| Rc::ptr_eq(class, &ty)),
+        )),
+        Value::Trait(ty) => Ok(Value::Boolean(
+            class.is_some_and(|class| class.implements(&ty)),
+        )),
+        ty => dump!(
+            RuntimeError::NotAClassOrTrait::<&str>(ty.into()),
+            op.span.clone()
+        ),
+    }
+}
+
+/// The union, intersection or difference of two sets
+fn sets(op: &Token, left: Value, right: Value) -> Result<Value> {
+    let (Value::Set(a), Value::Set(b)) = (&left, &right) else {
+        dump!(
+            RuntimeError::InvalidTypes(
+                op.lex(),
+                vec![left.into(), right.into()],
+                vec![(Type::Set, Type::Set)]
+            ),
+            op.span.clone()
+        );
+    };
+    let result = match op.kind {
+        TokenKind::Bar => a.union(b),
+        TokenKind::Ampersand => a.intersection(b),
+        _ => a.difference(b),
+    };
+    Ok(Value::Set(result))
+}
+
+// exact comparison is what `==` means for floats, as in IEEE 754
+#[allow(clippy::float_cmp)]
+pub(super) fn equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => a == b,
+        #[cfg(feature = "decimal")]
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => decimal(left)
+            .zip(decimal(right))
+            .is_some_and(|(a, b)| a == b),
+        _ => match (float(left), float(right)) {
+            (Some(a), Some(b)) => a == b,
+            _ => left == right,
+        },
+    }
+}
+
+fn compare(op: &Token, left: Value, right: Value) -> Result<Value> {
+    let ordering = match (&left, &right) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        #[cfg(feature = "decimal")]
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            let (Some(a), Some(b)) = (decimal(&left), decimal(&right)) else {
+                return Err(incomparable(op, left, right).into());
+            };
+            Some(a.cmp(&b))
+        }
+        #[cfg(feature = "units")]
+        (Value::Quantity(_), _) | (_, Value::Quantity(_)) => {
+            let (a, b) = measured(op, &left, &right)?;
+            a.magnitude.partial_cmp(&b.magnitude)
+        }
+        _ => match (float(&left), float(&right)) {
+            // `None` when either is NaN, which makes every comparison false
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => return Err(incomparable(op, left, right).into()),
+        },
+    };
+
+    let result = ordering.is_some_and(|ordering| match op.kind {
+        TokenKind::Greater => ordering == Ordering::Greater,
+        TokenKind::GreaterEqual => ordering != Ordering::Less,
+        TokenKind::Less => ordering == Ordering::Less,
+        _ => ordering != Ordering::Greater,
+    });
+    Ok(Value::Boolean(result))
+}
+
+/// The error for ordering operands of the wrong types with `op`
+fn incomparable(op: &Token, left: Value, right: Value) -> Diagnostic<RuntimeError<String>> {
+    let mut expected = numeric_pairs();
+    expected.push((Type::String, Type::String));
+
+    diagnostic!(
+        RuntimeError::InvalidTypes(op.lex(), vec![left.into(), right.into()], expected),
+        op.span.clone()
+    )
+}
+
+fn arithmetic(op: &Token, left: Value, right: Value) -> Result<Value> {
+    match (&left, &right) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            let result = match op.kind {
+                TokenKind::Plus => a.checked_add(*b),
+                TokenKind::Minus => a.checked_sub(*b),
+                TokenKind::Star => a.checked_mul(*b),
+                _ if *b == 0 => dump!(RuntimeError::DivisionByZero::<&str>, op.span.clone()),
+                _ => a.checked_div(*b),
+            };
+
+            match result {
+                Some(result) => Ok(Value::Integer(result)),
+                None => dump!(RuntimeError::IntegerOverflow(op.lex()), op.span.clone()),
+            }
+        }
+        #[cfg(feature = "decimal")]
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            let (Some(a), Some(b)) = (decimal(&left), decimal(&right)) else {
+                return Err(invalid_types(op, left, right).into());
+            };
+            let result = match op.kind {
+                TokenKind::Plus => a.checked_add(b),
+                TokenKind::Minus => a.checked_sub(b),
+                TokenKind::Star => a.checked_mul(b),
+                _ if b.is_zero() => dump!(RuntimeError::DivisionByZero::<&str>, op.span.clone()),
+                _ => a.checked_div(b),
+            };
+
+            match result {
+                Some(result) => Ok(Value::Decimal(result)),
+                None => dump!(RuntimeError::DecimalOverflow(op.lex()), op.span.clone()),
+            }
+        }
+        #[cfg(feature = "units")]
+        (Value::Quantity(_), _) | (_, Value::Quantity(_)) => {
+            let result = match op.kind {
+                TokenKind::Star | TokenKind::Slash => {
+                    let (Some(a), Some(b)) = (quantity(&left), quantity(&right)) else {
+                        return Err(invalid_types(op, left, right).into());
+                    };
+                    if op.kind == TokenKind::Star {
+                        a.times(b)
+                    } else {
+                        a.per(b)
+                    }
+                }
+                TokenKind::Plus => {
+                    let (a, b) = measured(op, &left, &right)?;
+                    Quantity {
+                        magnitude: a.magnitude + b.magnitude,
+                        ..a
+                    }
+                }
+                _ => {
+                    let (a, b) = measured(op, &left, &right)?;
+                    Quantity {
+                        magnitude: a.magnitude - b.magnitude,
+                        ..a
+                    }
+                }
+            };
+            Ok(result.into_value())
+        }
+        (Value::Set(_), Value::Set(_)) if op.kind == TokenKind::Minus => sets(op, left, right),
+        (Value::String(a), Value::String(b)) if op.kind == TokenKind::Plus => {
+            Ok(Value::String(format!("{a}{b}")))
+        }
+        _ => {
+            let (Some(a), Some(b)) = (float(&left), float(&right)) else {
+                return Err(invalid_types(op, left, right).into());
+            };
+
+            let result = match op.kind {
+                TokenKind::Plus => a + b,
+                TokenKind::Minus => a - b,
+                TokenKind::Star => a * b,
+                _ => a / b,
+            };
+            Ok(Value::Float(OrderedFloat(result)))
+        }
+    }
+}
+
+/// The value of a number as a float, integers being rounded to the nearest float
+#[allow(clippy::cast_precision_loss)]
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(int) => Some(*int as f64),
+        Value::Float(float) => Some(float.0),
+        _ => None,
+    }
+}
+
+/// The value of a number as a decimal, integers converting exactly
+///
+/// Floats are never converted, as mixing them with decimals would bring back the rounding errors
+/// that decimals are used to avoid.
+#[cfg(feature = "decimal")]
+fn decimal(value: &Value) -> Option<rust_decimal::Decimal> {
+    match value {
+        Value::Integer(int) => rust_decimal::Decimal::try_from_i128_with_scale(*int, 0).ok(),
+        Value::Decimal(decimal) => Some(*decimal),
+        _ => None,
+    }
+}
+
+/// The value of a number or quantity as a quantity, numbers having no units
+#[cfg(feature = "units")]
+#[allow(clippy::cast_precision_loss)]
+fn quantity(value: &Value) -> Option<Quantity> {
+    match value {
+        Value::Quantity(quantity) => Some(*quantity),
+        Value::Integer(int) => Some(Quantity::scalar(*int as f64)),
+        Value::Float(float) => Some(Quantity::scalar(float.0)),
+        _ => None,
+    }
+}
+
+/// The operands of `op` as quantities, which must be measured in the same units
+#[cfg(feature = "units")]
+fn measured(op: &Token, left: &Value, right: &Value) -> Result<(Quantity, Quantity)> {
+    let (Some(a), Some(b)) = (quantity(left), quantity(right)) else {
+        return Err(invalid_types(op, left.clone(), right.clone()).into());
+    };
+    if a.dimension != b.dimension {
+        dump!(
+            RuntimeError::DimensionMismatch(
+                op.lex(),
+                a.dimension.to_string(),
+                b.dimension.to_string()
+            ),
+            op.span.clone()
+        );
+    }
+    Ok((a, b))
+}
+
+/// The pairs of operand types that arithmetic and comparisons accept
+fn numeric_pairs() -> Vec<(Type, Type)> {
+    vec![
+        (Type::Integer, Type::Integer),
+        (Type::Float, Type::Float),
+        (Type::Integer, Type::Float),
+        (Type::Float, Type::Integer),
+        #[cfg(feature = "decimal")]
+        (Type::Decimal, Type::Decimal),
+        #[cfg(feature = "decimal")]
+        (Type::Integer, Type::Decimal),
+        #[cfg(feature = "decimal")]
+        (Type::Decimal, Type::Integer),
+        #[cfg(feature = "units")]
+        (Type::Quantity, Type::Quantity),
+    ]
+}
+
+/// The error for applying the arithmetic operator `op` to operands of the wrong types
+pub(super) fn invalid_types(
+    op: &Token,
+    left: Value,
+    right: Value,
+) -> Diagnostic<RuntimeError<String>> {
+    let mut expected = numeric_pairs();
+    match op.kind {
+        TokenKind::Plus => expected.push((Type::String, Type::String)),
+        TokenKind::Minus => expected.push((Type::Set, Type::Set)),
+        _ => (),
+    }
+
+    diagnostic!(
+        RuntimeError::InvalidTypes(op.lex(), vec![left.into(), right.into()], expected),
+        op.span.clone()
+    )
+}
+
+/// `left + right` where exactly one operand is a string, the other being converted to a string
+pub(super) fn coerce(left: &Value, right: &Value) -> Option<Value> {
+    match (left, right) {
+        (Value::String(_), Value::String(_)) => None,
+        (Value::String(_), _) | (_, Value::String(_)) => {
+            Some(Value::String(format!("{left}{right}")))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,101 @@
+//! Matching the value of a `match` against the patterns of its arms
+//!
+//! String patterns bind variables in the body of their arm: the rest of the string for prefix and
+//! suffix patterns, and each named group for `regex` patterns, which is `nil` if the group did
+//! not take part in the match.
+use color_eyre::Report;
+use std::slice;
+
+use super::{ops, Flow, Interpreter};
+use crate::{
+    ast::{Arm, Expr, Pattern},
+    environment::Env,
+    token::{Token, Value},
+};
+
+impl Interpreter {
+    /// Executes the body of the first of `arms` whose pattern matches the value of `value`, in a
+    /// scope of its own if the pattern binds any variables
+    pub(super) fn match_arms(&self, value: &Expr, arms: &[Arm]) -> Result<Flow, Vec<Report>> {
+        let value = self.expression(value).map_err(|err| vec![err])?;
+
+        for arm in arms {
+            let Some(bindings) = self.bindings(&arm.pattern, &value) else {
+                continue;
+            };
+            if bindings.is_empty() {
+                return self.execute(&arm.body);
+            }
+            let mut env = Env::with_parent(self.scope());
+            for (name, value) in bindings {
+                env.define(name, Some(value));
+            }
+            return self.execute_block(slice::from_ref(&arm.body), env);
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// The variables that `pattern` binds if it matches `value`, or `None` if it does not
+    fn bindings(&self, pattern: &Pattern, value: &Value) -> Option<Vec<(Token, Value)>> {
+        let rest = |name: &Token, rest: &str| match name.lex().as_str() {
+            "_" => vec![],
+            _ => vec![(name.clone(), Value::String(rest.to_string()))],
+        };
+
+        match (pattern, value) {
+            (Pattern::Literal(literal), value) => literal
+                .literal
+                .as_ref()
+                .filter(|literal| ops::equal(value, literal))
+                .map(|_| vec![]),
+            (Pattern::Wildcard(_), _) => Some(vec![]),
+            (Pattern::Prefix { prefix, rest: name }, Value::String(string)) => string
+                .strip_prefix(text(prefix))
+                .map(|remainder| rest(name, remainder)),
+            (Pattern::Suffix { rest: name, suffix }, Value::String(string)) => string
+                .strip_suffix(text(suffix))
+                .map(|remainder| rest(name, remainder)),
+            (Pattern::Regex { pattern, .. }, Value::String(string)) => {
+                self.captures(text(pattern), string)
+            }
+            (Pattern::Prefix { .. } | Pattern::Suffix { .. } | Pattern::Regex { .. }, _) => None,
+        }
+    }
+
+    /// The named groups of `regex` where it first matches `string`, or `None` if it does not
+    #[cfg(feature = "regex")]
+    fn captures(&self, regex: &str, string: &str) -> Option<Vec<(Token, Value)>> {
+        let mut regexes = self.regexes.borrow_mut();
+        let regex = regexes
+            .entry(regex.to_string())
+            .or_insert_with(|| regex::Regex::new(regex).expect("the parser checks regexes"));
+
+        let captures = regex.captures(string)?;
+        let bindings = regex
+            .capture_names()
+            .flatten()
+            .map(|name| {
+                let value = captures.name(name).map_or(Value::Null, |group| {
+                    Value::String(group.as_str().to_string())
+                });
+                (super::ident(name), value)
+            })
+            .collect();
+        Some(bindings)
+    }
+
+    /// Without the regex feature the parser rejects `regex` patterns, so they never match
+    #[cfg(not(feature = "regex"))]
+    #[allow(clippy::unused_self)]
+    fn captures(&self, _: &str, _: &str) -> Option<Vec<(Token, Value)>> {
+        None
+    }
+}
+
+/// The text of the string literal in a pattern
+fn text(tok: &Token) -> &str {
+    match &tok.literal {
+        Some(Value::String(string)) => string,
+        _ => unreachable!("string patterns are made of string literals"),
+    }
+}
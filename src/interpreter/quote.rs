@@ -0,0 +1,307 @@
+use color_eyre::Result;
+use std::rc::Rc;
+
+use super::Interpreter;
+use crate::{
+    ast::{Arm, Expr, FunDecl, Quoted, Stmt},
+    dump,
+    error::RuntimeError,
+    token::{Token, TokenKind, Value},
+};
+
+impl Interpreter {
+    /// Evaluates a `quote`, producing a copy of its syntax tree in which every `unquote` has been
+    /// replaced by the value of its operand
+    pub(super) fn quote(&self, quoted: &Quoted) -> Result<Value> {
+        let quoted = match quoted {
+            Quoted::Expr(expr) => Quoted::Expr(self.splice_expr(expr)?),
+            Quoted::Stmts(stmts) => Quoted::Stmts(
+                stmts
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+            ),
+        };
+        Ok(Value::Ast(Box::new(quoted)))
+    }
+
+    fn splice_stmt(&self, stmt: &Stmt) -> Result<Stmt> {
+        Ok(match stmt {
+            // a quoted list of statements spliced in statement position runs as a block
+            Stmt::Expr(Expr::Unquote(keyword, expr)) => match self.expression(expr)? {
+                Value::Ast(quoted) => match *quoted {
+                    Quoted::Expr(expr) => Stmt::Expr(expr),
+                    Quoted::Stmts(stmts) => Stmt::Block(stmts),
+                },
+                value => Stmt::Expr(Expr::Literal(literal(value, keyword))),
+            },
+            Stmt::Expr(expr) => Stmt::Expr(self.splice_expr(expr)?),
+            Stmt::Print(expr) => Stmt::Print(self.splice_expr(expr)?),
+            Stmt::Block(stmts) => Stmt::Block(
+                stmts
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+            ),
+            Stmt::Var { name, value } => Stmt::Var {
+                name: name.clone(),
+                value: value
+                    .as_ref()
+                    .map(|value| self.splice_expr(value))
+                    .transpose()?,
+            },
+            Stmt::VarTuple { names, value } => Stmt::VarTuple {
+                names: names.clone(),
+                value: self.splice_expr(value)?,
+            },
+            Stmt::Function { decorators, decl } => Stmt::Function {
+                decorators: decorators
+                    .iter()
+                    .map(|decorator| self.splice_expr(decorator))
+                    .collect::<Result<_>>()?,
+                decl: self.splice_decl(decl)?,
+            },
+            Stmt::Class {
+                name,
+                doc,
+                methods,
+                statics,
+                getters,
+                setters,
+                traits,
+            } => Stmt::Class {
+                name: name.clone(),
+                doc: doc.clone(),
+                methods: methods
+                    .iter()
+                    .map(|decl| self.splice_decl(decl))
+                    .collect::<Result<_>>()?,
+                statics: statics
+                    .iter()
+                    .map(|decl| self.splice_decl(decl))
+                    .collect::<Result<_>>()?,
+                getters: getters
+                    .iter()
+                    .map(|decl| self.splice_decl(decl))
+                    .collect::<Result<_>>()?,
+                setters: setters
+                    .iter()
+                    .map(|decl| self.splice_decl(decl))
+                    .collect::<Result<_>>()?,
+                traits: traits.clone(),
+            },
+            // traits contain no expressions to splice
+            Stmt::Trait { .. } => stmt.clone(),
+            Stmt::Defer(expr) => Stmt::Defer(self.splice_expr(expr)?),
+            Stmt::With {
+                keyword,
+                name,
+                value,
+                body,
+            } => Stmt::With {
+                keyword: keyword.clone(),
+                name: name.clone(),
+                value: self.splice_expr(value)?,
+                body: body
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+            },
+            Stmt::Match {
+                keyword,
+                value,
+                arms,
+            } => Stmt::Match {
+                keyword: keyword.clone(),
+                value: self.splice_expr(value)?,
+                arms: arms
+                    .iter()
+                    .map(|arm| {
+                        Ok(Arm {
+                            pattern: arm.pattern.clone(),
+                            body: self.splice_stmt(&arm.body)?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            },
+            Stmt::Throw { keyword, value } => Stmt::Throw {
+                keyword: keyword.clone(),
+                value: self.splice_expr(value)?,
+            },
+            Stmt::Try {
+                body,
+                name,
+                handler,
+            } => Stmt::Try {
+                body: body
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+                name: name.clone(),
+                handler: handler
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+            },
+            Stmt::While {
+                keyword,
+                condition,
+                body,
+            } => Stmt::While {
+                keyword: keyword.clone(),
+                condition: self.splice_expr(condition)?,
+                body: Box::new(self.splice_stmt(body)?),
+            },
+            Stmt::ForIn {
+                keyword,
+                name,
+                iterable,
+                body,
+            } => Stmt::ForIn {
+                keyword: keyword.clone(),
+                name: name.clone(),
+                iterable: self.splice_expr(iterable)?,
+                body: body
+                    .iter()
+                    .map(|stmt| self.splice_stmt(stmt))
+                    .collect::<Result<_>>()?,
+            },
+            Stmt::DoWhile {
+                body,
+                keyword,
+                condition,
+            } => Stmt::DoWhile {
+                body: Box::new(self.splice_stmt(body)?),
+                keyword: keyword.clone(),
+                condition: self.splice_expr(condition)?,
+            },
+            Stmt::Return { keyword, value } => Stmt::Return {
+                keyword: keyword.clone(),
+                value: value
+                    .as_ref()
+                    .map(|value| self.splice_expr(value))
+                    .transpose()?,
+            },
+        })
+    }
+
+    fn splice_expr(&self, expr: &Expr) -> Result<Expr> {
+        let splice = |expr: &Expr| self.splice_expr(expr).map(Box::new);
+
+        Ok(match expr {
+            Expr::Unquote(keyword, expr) => match self.expression(expr)? {
+                Value::Ast(quoted) => match *quoted {
+                    Quoted::Expr(expr) => expr,
+                    quoted @ Quoted::Stmts(_) => {
+                        Expr::Literal(literal(Value::Ast(Box::new(quoted)), keyword))
+                    }
+                },
+                value => Expr::Literal(literal(value, keyword)),
+            },
+            Expr::Binary(left, op, right) => {
+                Expr::Binary(splice(left)?, op.clone(), splice(right)?)
+            }
+            Expr::Logical(left, op, right) => {
+                Expr::Logical(splice(left)?, op.clone(), splice(right)?)
+            }
+            Expr::Grouping(expr) => Expr::Grouping(splice(expr)?),
+            Expr::Unary(op, expr) => Expr::Unary(op.clone(), splice(expr)?),
+            Expr::Assignment(name, expr) => Expr::Assignment(name.clone(), splice(expr)?),
+            Expr::Call(callee, paren, args, named) => Expr::Call(
+                splice(callee)?,
+                paren.clone(),
+                args.iter()
+                    .map(|arg| self.splice_expr(arg))
+                    .collect::<Result<_>>()?,
+                named
+                    .iter()
+                    .map(|(name, arg)| Ok((name.clone(), self.splice_expr(arg)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Expr::Get(object, name) => Expr::Get(splice(object)?, name.clone()),
+            Expr::Set(object, name, value) => {
+                Expr::Set(splice(object)?, name.clone(), splice(value)?)
+            }
+            Expr::Index(indexed, bracket, index) => {
+                Expr::Index(splice(indexed)?, bracket.clone(), splice(index)?)
+            }
+            Expr::SetLiteral(keyword, elements) => Expr::SetLiteral(
+                keyword.clone(),
+                elements
+                    .iter()
+                    .map(|element| self.splice_expr(element))
+                    .collect::<Result<_>>()?,
+            ),
+            Expr::Tuple(paren, elements) => Expr::Tuple(
+                paren.clone(),
+                elements
+                    .iter()
+                    .map(|element| self.splice_expr(element))
+                    .collect::<Result<_>>()?,
+            ),
+            Expr::Destructure(names, value) => Expr::Destructure(names.clone(), splice(value)?),
+            // nested quotes are spliced when they themselves are evaluated
+            Expr::Literal(_) | Expr::Variable(_) | Expr::This(_) | Expr::Quote(_) => expr.clone(),
+        })
+    }
+
+    fn splice_decl(&self, decl: &FunDecl) -> Result<Rc<FunDecl>> {
+        Ok(Rc::new(FunDecl {
+            name: decl.name.clone(),
+            doc: decl.doc.clone(),
+            params: decl.params.clone(),
+            defaults: decl
+                .defaults
+                .iter()
+                .map(|default| self.splice_expr(default))
+                .collect::<Result<_>>()?,
+            rest: decl.rest.clone(),
+            body: decl
+                .body
+                .iter()
+                .map(|stmt| self.splice_stmt(stmt))
+                .collect::<Result<_>>()?,
+        }))
+    }
+
+    /// Evaluates an `unquote` that is not inside of a quote
+    pub(super) fn unquote(keyword: &Token) -> Result<Value> {
+        dump!(
+            RuntimeError::UnquoteOutsideQuote::<&str>,
+            keyword.span.clone()
+        )
+    }
+}
+
+/// Creates a literal token holding `value`, located at the `unquote` that produced it
+fn literal(value: Value, keyword: &Token) -> Token {
+    let kind = match value {
+        Value::String(_) => TokenKind::String,
+        Value::Integer(_) | Value::Float(_) => TokenKind::Number,
+        #[cfg(feature = "decimal")]
+        Value::Decimal(_) => TokenKind::Number,
+        #[cfg(feature = "units")]
+        Value::Quantity(_) => TokenKind::Number,
+        Value::Boolean(true) => TokenKind::True,
+        Value::Boolean(false) => TokenKind::False,
+        Value::Null => TokenKind::Nil,
+        Value::NativeFn(_)
+        | Value::Function(_)
+        | Value::Class(_)
+        | Value::Trait(_)
+        | Value::Instance(_)
+        | Value::Ast(_)
+        | Value::List(_)
+        | Value::Range(..)
+        | Value::Tuple(_)
+        | Value::Map(_)
+        | Value::Set(_) => TokenKind::Identifier,
+    };
+
+    let mut span = keyword.span.clone();
+    span.lex = match &value {
+        Value::String(string) => format!("\"{string}\""),
+        value => value.to_string(),
+    };
+    Token::new(kind, Some(value), span)
+}
@@ -0,0 +1,146 @@
+//! A protocol for evaluating code cells one after another, as notebooks do, spoken as JSON over
+//! stdio by `atium kernel`
+//!
+//! Each line of input is a cell, and each produces one line of output, its reply:
+//!
+//! ```text
+//! {"id": 1, "code": "var x = 2; print x; x * 3"}
+//! {"id":1,"output":"2\n","value":"6","diagnostics":[]}
+//! ```
+//!
+//! Cells share one global scope, so later cells see the definitions of earlier ones. `id` is
+//! optional and may be any JSON value; it is returned unchanged so that clients can match replies
+//! to cells. `value` is what the REPL would echo for the final expression statement of the cell,
+//! or `null` if there is nothing to echo. Errors are listed in `diagnostics`, along with where they
+//! occurred, if that is known.
+use std::{
+    cell::RefCell,
+    io::{BufRead, Write},
+    mem,
+    rc::Rc,
+};
+
+use color_eyre::{Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atium::{global_env, Atium, Options, Output},
+    error::describe,
+    token::Value,
+};
+
+/// Code sent to be evaluated
+#[derive(Deserialize, Debug)]
+pub struct Cell {
+    #[serde(default)]
+    pub id: serde_json::Value,
+    pub code: String,
+}
+
+/// The result of evaluating a [`Cell`]
+#[derive(Serialize, Debug, Default)]
+pub struct Reply {
+    pub id: serde_json::Value,
+    /// everything that the cell printed
+    pub output: String,
+    pub value: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// An error produced by a cell
+#[derive(Serialize, Debug)]
+pub struct Diagnostic {
+    /// the name of the variant of the error, such as `DivisionByZero`
+    pub kind: &'static str,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl From<&Report> for Diagnostic {
+    fn from(report: &Report) -> Self {
+        let described = describe(report);
+        let span = described.span;
+        Self {
+            kind: described.kind,
+            message: described.message,
+            file: span
+                .as_ref()
+                .and_then(|span| span.file.as_ref())
+                .map(|file| file.name().display().to_string()),
+            line: span.as_ref().map(|span| span.line.0),
+            column: span.as_ref().map(|span| span.column.0),
+        }
+    }
+}
+
+/// Reads cells from `input` until it ends, writing a reply to `output` for each
+///
+/// A line that is not a valid cell is replied to with an `InvalidCell` diagnostic, rather than
+/// stopping the kernel.
+pub fn serve(input: impl BufRead, mut output: impl Write, options: &Options) -> Result<()> {
+    let buf = Rc::new(RefCell::new(String::new()));
+    let options = Options {
+        echo: false,
+        output: Output::Buffer(Rc::clone(&buf)),
+        ..options.clone()
+    };
+    let env = match global_env(&options) {
+        Ok(env) => env,
+        Err(errs) => {
+            let reply = Reply {
+                diagnostics: errs.iter().map(Diagnostic::from).collect(),
+                ..Reply::default()
+            };
+            writeln!(output, "{}", serde_json::to_string(&reply)?)?;
+            return Ok(());
+        }
+    };
+
+    for (idx, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut reply = match serde_json::from_str::<Cell>(&line) {
+            Ok(cell) => {
+                // each cell is a source of its own, that diagnostics can point into
+                let name = format!("cell:{}", idx + 1);
+                let result = Atium::with_env(&cell.code, Some(&name), Rc::clone(&env))
+                    .with_options(options.clone())
+                    .lex()
+                    .and_then(Atium::parse)
+                    .and_then(Atium::evaluate);
+                match result {
+                    Ok(value) => Reply {
+                        id: cell.id,
+                        value: (value != Value::Null).then(|| value.nested()),
+                        ..Reply::default()
+                    },
+                    Err(errs) => Reply {
+                        id: cell.id,
+                        diagnostics: errs.iter().map(Diagnostic::from).collect(),
+                        ..Reply::default()
+                    },
+                }
+            }
+            Err(err) => Reply {
+                diagnostics: vec![Diagnostic {
+                    kind: "InvalidCell",
+                    message: err.to_string(),
+                    file: None,
+                    line: None,
+                    column: None,
+                }],
+                ..Reply::default()
+            },
+        };
+        reply.output = mem::take(&mut *buf.borrow_mut());
+
+        writeln!(output, "{}", serde_json::to_string(&reply)?)?;
+        output.flush()?;
+    }
+    Ok(())
+}
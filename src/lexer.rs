@@ -1,33 +1,34 @@
 use color_eyre::{Report, Result};
 
-use std::{
-    collections::HashMap,
-    iter::Peekable,
-    path::{Path, PathBuf},
-    str::Chars,
-};
+use std::{collections::VecDeque, iter::Peekable, path::Path, str::Chars};
 
 use crate::{
-    error::{Column, Line, Span, SyntaxError},
+    diagnostic,
+    error::{source_map::FileId, Column, Diagnostic, Line, Span, SyntaxError},
     token::{Token, TokenKind, Value},
 };
 
 /// Contains a peekable iterator over a stream of characters (the source code).
 ///
-/// The source code is converted into a stream of tokens.
-pub(super) struct Cursor<'a> {
+/// The source code is converted into a stream of tokens, which the cursor yields one at a time as
+/// an iterator, or all at once through [`Cursor::lex`]. Tools that only need tokens, such as
+/// syntax highlighters, can use it without running the rest of the pipeline.
+pub struct Cursor<'a> {
     /// peekable iterator over stream of chars
     iter: Peekable<Chars<'a>>,
-    /// tokens present in source code
-    tokens: Vec<Token>,
-    /// the file that the cursor is iterating over (None == repl)
-    file: Option<PathBuf>,
-    /// reserved keywords for the language
-    reserved: HashMap<String, TokenKind>,
-    /// errors present in the source code
-    errors: Vec<Report>,
+    /// tokens and errors that have been lexed but not yet yielded
+    pending: VecDeque<Result<Token, Diagnostic<SyntaxError>>>,
+    /// the most tokens that will be yielded, after which lexing stops with an error
+    max_tokens: Option<usize>,
+    /// the number of tokens yielded so far
+    yielded: usize,
+    /// the source that the cursor is iterating over, if it has a name that errors can refer to it
+    /// by
+    file: Option<FileId>,
     /// offset from start of file
     offset: u32,
+    /// offset of the first character of the token currently being lexed
+    start: u32,
     /// offset of beggining of current line
     line_start: u32,
     /// current line number
@@ -41,82 +42,123 @@ impl<'a> Cursor<'a> {
     {
         Self {
             iter: src.chars().peekable(),
-            file: file.map(|inner| PathBuf::from(inner.as_ref())),
-            tokens: Vec::default(),
-            errors: Vec::default(),
+            file: file.map(|name| FileId::new(name, src)),
+            pending: VecDeque::new(),
+            max_tokens: None,
+            yielded: 0,
             offset: 0,
+            start: 0,
             line_start: 0,
             line: 0,
-            reserved: HashMap::from([
-                (String::from("and"), TokenKind::And),
-                (String::from("class"), TokenKind::Class),
-                (String::from("else"), TokenKind::Else),
-                (String::from("false"), TokenKind::False),
-                (String::from("fun"), TokenKind::Fun),
-                (String::from("for"), TokenKind::For),
-                (String::from("if"), TokenKind::If),
-                (String::from("nil"), TokenKind::Nil),
-                (String::from("or"), TokenKind::Or),
-                (String::from("print"), TokenKind::Print),
-                (String::from("return"), TokenKind::Return),
-                (String::from("super"), TokenKind::Super),
-                (String::from("this"), TokenKind::This),
-                (String::from("true"), TokenKind::True),
-                (String::from("var"), TokenKind::Var),
-                (String::from("while"), TokenKind::While),
-            ]),
         }
     }
 
     pub fn add_token(&mut self, kind: TokenKind, lex: String, lit: Option<Value>) {
-        let span = Span {
+        let token: Token = Token::new(kind, lit, self.span(lex));
+        self.pending.push_back(Ok(token));
+    }
+
+    /// Creates a span starting at the beginning of the current token
+    fn span(&self, lex: String) -> Span {
+        Span {
             line: Line(self.line + 1),
-            column: Column(self.offset - self.line_start),
+            column: Column(self.start - self.line_start + 1),
+            start: self.start,
+            end: self.offset,
             file: self.file.clone(),
             lex,
-        };
-        let token: Token = Token::new(kind, lit, span);
-        self.tokens.push(token);
-    }
-
-    pub fn lex(mut self) -> Result<Vec<Token>, Vec<Report>> {
-        while let Some(c) = self.iter.next() {
-            self.offset += 1;
-            match c {
-                '(' => self.add_token(TokenKind::LeftParen, c.to_string(), None),
-                ')' => self.add_token(TokenKind::RightParen, c.to_string(), None),
-                '{' => self.add_token(TokenKind::LeftBrace, c.to_string(), None),
-                '}' => self.add_token(TokenKind::RightBrace, c.to_string(), None),
-                ',' => self.add_token(TokenKind::Comma, c.to_string(), None),
-                '.' => self.add_token(TokenKind::Dot, c.to_string(), None),
-                '-' => self.add_token(TokenKind::Minus, c.to_string(), None),
-                '+' => self.add_token(TokenKind::Plus, c.to_string(), None),
-                ';' => self.add_token(TokenKind::Semicolon, c.to_string(), None),
-                '*' => self.add_token(TokenKind::Star, c.to_string(), None),
-                '!' => self.branching_char(c, '=', TokenKind::BangEqual, TokenKind::Bang),
-                '=' => self.branching_char(c, '=', TokenKind::EqualEqual, TokenKind::Equal),
-                '<' => self.branching_char(c, '=', TokenKind::LessEqual, TokenKind::Less),
-                '>' => self.branching_char(c, '=', TokenKind::GreaterEqual, TokenKind::Greater),
-                '/' => self.handle_comment(c),
-                '"' => self.handle_string(),
-                '0'..='9' => self.handle_number(c),
-                'a'..='z' | 'A'..='Z' => self.handle_ident(c),
-                '\n' => {
-                    self.line_start = self.offset;
-                    self.line += 1;
-                }
-                '\r' | '\t' | ' ' => (),
-                _ => self.errors.push(SyntaxError::UnexpectedCharacter(c).into()),
+        }
+    }
+
+    /// Stops lexing with an error once more than `max` tokens have been lexed
+    #[must_use]
+    pub fn with_max_tokens(mut self, max: Option<usize>) -> Self {
+        self.max_tokens = max;
+        self
+    }
+
+    /// Lexes the whole of the source, collecting every error rather than stopping at the first
+    pub fn lex(self) -> Result<Vec<Token>, Vec<Report>> {
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        for token in self {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(err) => errors.push(err.into()),
             }
         }
 
-        if self.errors.is_empty() {
-            Ok(self.tokens)
+        if errors.is_empty() {
+            Ok(tokens)
         } else {
-            Err(self.errors)
+            Err(errors)
         }
     }
 
+    /// Lexes the token starting with `c`, which has just been consumed
+    fn lex_char(&mut self, c: char) {
+        match c {
+            '(' => self.add_token(TokenKind::LeftParen, c.to_string(), None),
+            ')' => self.add_token(TokenKind::RightParen, c.to_string(), None),
+            '{' => self.add_token(TokenKind::LeftBrace, c.to_string(), None),
+            '}' => self.add_token(TokenKind::RightBrace, c.to_string(), None),
+            '[' => self.add_token(TokenKind::LeftBracket, c.to_string(), None),
+            ']' => self.add_token(TokenKind::RightBracket, c.to_string(), None),
+            ',' => self.add_token(TokenKind::Comma, c.to_string(), None),
+            ':' => self.add_token(TokenKind::Colon, c.to_string(), None),
+            '.' if self.iter.clone().take(2).eq(['.', '.']) => {
+                self.bump();
+                self.bump();
+                self.add_token(TokenKind::Ellipsis, String::from("..."), None);
+            }
+            '.' => self.add_token(TokenKind::Dot, c.to_string(), None),
+            '-' => self.add_token(TokenKind::Minus, c.to_string(), None),
+            '+' => self.add_token(TokenKind::Plus, c.to_string(), None),
+            ';' => self.add_token(TokenKind::Semicolon, c.to_string(), None),
+            '*' => self.add_token(TokenKind::Star, c.to_string(), None),
+            '@' => self.add_token(TokenKind::At, c.to_string(), None),
+            '!' => self.branching_char(c, '=', TokenKind::BangEqual, TokenKind::Bang),
+            '=' if self.iter.peek() == Some(&'>') => {
+                self.bump();
+                self.add_token(TokenKind::FatArrow, String::from("=>"), None);
+            }
+            '=' => self.branching_char(c, '=', TokenKind::EqualEqual, TokenKind::Equal),
+            '<' => self.branching_char(c, '=', TokenKind::LessEqual, TokenKind::Less),
+            '>' => self.branching_char(c, '=', TokenKind::GreaterEqual, TokenKind::Greater),
+            '|' if self.iter.peek() == Some(&'>') => {
+                self.bump();
+                self.add_token(TokenKind::PipeGreater, String::from("|>"), None);
+            }
+            '|' => self.add_token(TokenKind::Bar, c.to_string(), None),
+            '&' => self.add_token(TokenKind::Ampersand, c.to_string(), None),
+            '/' => self.handle_comment(c),
+            '"' => self.handle_string(),
+            '0'..='9' => self.handle_number(c),
+            c if is_ident_start(c) => self.handle_ident(c),
+            '\n' => self.newline(),
+            // a CRLF pair is a single line break, the '\n' arm handles it
+            '\r' if self.iter.peek() == Some(&'\n') => (),
+            '\r' | '\t' | ' ' => (),
+            _ => self.pending.push_back(Err(diagnostic!(
+                SyntaxError::UnexpectedCharacter(c),
+                self.span(c.to_string())
+            ))),
+        }
+    }
+
+    /// Advances the underlying iterator, keeping the offset in sync with it
+    fn bump(&mut self) -> Option<char> {
+        let c = self.iter.next()?;
+        self.offset += 1;
+        Some(c)
+    }
+
+    /// Records that a line break has just been consumed
+    fn newline(&mut self) {
+        self.line_start = self.offset;
+        self.line += 1;
+    }
+
     pub fn branching_char(
         &mut self,
         curr: char,
@@ -124,25 +166,35 @@ impl<'a> Cursor<'a> {
         success: TokenKind,
         failure: TokenKind,
     ) {
-        match self.iter.peek() {
-            Some(x) if *x == next => {
-                self.iter.next().unwrap();
-                self.add_token(success, format!("{curr}{next}"), None);
-            }
-            Some(_) => self.add_token(failure, next.to_string(), None),
-            None => (),
+        if self.iter.peek() == Some(&next) {
+            self.bump();
+            self.add_token(success, format!("{curr}{next}"), None);
+        } else {
+            self.add_token(failure, curr.to_string(), None);
         }
     }
 
+    /// Lexes an identifier or keyword, the first character of which is `curr`
+    ///
+    /// ```text
+    /// identifier = start , { start | digit } ;
+    /// start = "a" .. "z" | "A" .. "Z" | "_" ;
+    /// digit = "0" .. "9" ;
+    /// ```
+    ///
+    /// Identifiers spelt like a keyword are lexed as that keyword.
     pub fn handle_ident(&mut self, curr: char) {
         let mut ident = vec![curr];
-        while let Some('a'..='z' | 'A'..='Z' | '1'..='9') = self.iter.peek() {
-            ident.push(self.iter.next().unwrap());
+        while let Some(&c) = self.iter.peek() {
+            if !is_ident_continue(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump();
         }
 
         let ident = ident.into_iter().collect::<String>();
-        if self.reserved.contains_key(&ident) {
-            let tt = self.reserved.get(&ident).unwrap().clone();
+        if let Some(tt) = TokenKind::keyword(&ident) {
             match tt {
                 TokenKind::True => {
                     self.add_token(tt, ident, Some(true.into()));
@@ -150,6 +202,9 @@ impl<'a> Cursor<'a> {
                 TokenKind::False => {
                     self.add_token(tt, ident, Some(false.into()));
                 }
+                TokenKind::Nil => {
+                    self.add_token(tt, ident, Some(Value::Null));
+                }
                 _ => self.add_token(tt, ident, None),
             }
         } else {
@@ -157,16 +212,40 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    /// Lexes a number, the first digit of which is `curr`
+    ///
+    /// A dot is only part of the number if it is the first and is followed by a digit, so `1.abs()`
+    /// is a method call on `1` and `1.2.3` is `1.2` followed by `.3`. An exponent, as in `1e20` or
+    /// `2.5E-3`, makes the number a float, and is only part of it if there are digits after the
+    /// `e` and its optional sign, so floats print in a form that lexes back to the same number.
     pub fn handle_number(&mut self, curr: char) {
         let mut num = vec![curr];
         let mut float = false;
 
         loop {
-            match self.iter.peek() {
-                Some('0'..='9') => num.push(self.iter.next().unwrap()),
-                Some('.') => {
+            match self.iter.peek().copied() {
+                Some('0'..='9') => num.push(self.bump().unwrap()),
+                Some('.')
+                    if !float && self.iter.clone().nth(1).is_some_and(|c| c.is_ascii_digit()) =>
+                {
                     float = true;
-                    num.push(self.iter.next().unwrap());
+                    num.push(self.bump().unwrap());
+                }
+                Some('e' | 'E') => {
+                    let mut exponent = self.iter.clone().skip(1).peekable();
+                    let sign = exponent.next_if(|&c| c == '+' || c == '-');
+                    if !exponent.peek().is_some_and(char::is_ascii_digit) {
+                        break;
+                    }
+                    float = true;
+                    num.push(self.bump().unwrap());
+                    if sign.is_some() {
+                        num.push(self.bump().unwrap());
+                    }
+                    while self.iter.peek().is_some_and(char::is_ascii_digit) {
+                        num.push(self.bump().unwrap());
+                    }
+                    break;
                 }
                 _ => break,
             }
@@ -175,7 +254,18 @@ impl<'a> Cursor<'a> {
         let pre_literal = num.into_iter().collect::<String>();
         let lexeme = pre_literal.clone();
 
-        if float {
+        #[cfg(feature = "units")]
+        if self.handle_unit(&pre_literal) {
+            return;
+        }
+
+        // a `d` directly after the digits makes a decimal, unless it starts an identifier
+        if self.iter.peek() == Some(&'d')
+            && !self.iter.clone().nth(1).is_some_and(is_ident_continue)
+        {
+            self.bump();
+            self.handle_decimal(&pre_literal);
+        } else if float {
             self.add_token(
                 TokenKind::Number,
                 lexeme,
@@ -184,27 +274,89 @@ impl<'a> Cursor<'a> {
                 ))),
             );
         } else {
-            self.add_token(
-                TokenKind::Number,
-                lexeme,
-                Some(Value::Integer(pre_literal.parse::<i128>().unwrap())),
-            );
+            match pre_literal.parse::<i128>() {
+                Ok(int) => self.add_token(TokenKind::Number, lexeme, Some(Value::Integer(int))),
+                Err(_) => self.pending.push_back(Err(diagnostic!(
+                    SyntaxError::IntegerTooLarge(lexeme.clone()),
+                    self.span(lexeme)
+                ))),
+            }
         }
     }
 
+    /// Lexes the decimal literal `digits` followed by `d`, the suffix having been consumed
+    fn handle_decimal(&mut self, digits: &str) {
+        let lexeme = format!("{digits}d");
+
+        #[cfg(feature = "decimal")]
+        match digits.parse::<rust_decimal::Decimal>() {
+            Ok(decimal) => self.add_token(TokenKind::Number, lexeme, Some(Value::Decimal(decimal))),
+            Err(_) => self.pending.push_back(Err(diagnostic!(
+                SyntaxError::InvalidDecimal(lexeme.clone()),
+                self.span(lexeme)
+            ))),
+        }
+        #[cfg(not(feature = "decimal"))]
+        self.pending.push_back(Err(diagnostic!(
+            SyntaxError::DecimalsDisabled(lexeme.clone()),
+            self.span(lexeme)
+        )));
+    }
+
+    /// Lexes a quantity if the number `digits` is directly followed by the name of a unit, as in
+    /// `5km`, returning whether it was
+    #[cfg(feature = "units")]
+    fn handle_unit(&mut self, digits: &str) -> bool {
+        use crate::token::quantity::Quantity;
+
+        let unit = self
+            .iter
+            .clone()
+            .take_while(|&c| is_ident_continue(c))
+            .collect::<String>();
+        let Some(size) = Quantity::unit(&unit) else {
+            return false;
+        };
+        for _ in 0..unit.len() {
+            self.bump();
+        }
+
+        let quantity = Quantity::scalar(digits.parse().unwrap()).times(size);
+        self.add_token(
+            TokenKind::Number,
+            format!("{digits}{unit}"),
+            Some(Value::Quantity(quantity)),
+        );
+        true
+    }
+
+    /// Lexes a string, the opening quote of which has just been consumed
+    ///
+    /// Strings may span several lines, whose breaks are only recorded once the string has been
+    /// lexed, so that its span starts on the line that the string does.
     pub fn handle_string(&mut self) {
         let mut chars = vec!['"'];
+        let mut breaks = vec![];
         let (token, lit) = loop {
-            match self.iter.next() {
+            match self.bump() {
                 Some('"') => break (TokenKind::String, chars[1..].iter().collect::<String>()),
+                Some('\n') => {
+                    breaks.push(self.offset);
+                    chars.push('\n');
+                }
                 Some(char) => chars.push(char),
-                None => self.errors.push(
-                    SyntaxError::ExpectedCharacter {
-                        expected: '"',
-                        found: String::from("EOF"),
-                    }
-                    .into(),
-                ),
+                None => {
+                    let span = self.span(chars.into_iter().collect());
+                    self.pending.push_back(Err(diagnostic!(
+                        SyntaxError::ExpectedCharacter {
+                            expected: '"',
+                            found: String::from("EOF"),
+                        },
+                        span
+                    )));
+                    self.newlines(&breaks);
+                    return;
+                }
             }
         };
 
@@ -214,19 +366,88 @@ impl<'a> Cursor<'a> {
             chars.into_iter().collect::<String>(),
             Some(Value::String(lit)),
         );
+        self.newlines(&breaks);
+    }
+
+    /// Records the line breaks that were consumed at each of `offsets`
+    fn newlines(&mut self, offsets: &[u32]) {
+        for &offset in offsets {
+            self.line_start = offset;
+            self.line += 1;
+        }
     }
 
+    /// Skips over a `//` comment, or emits the text of a `///` doc comment
     pub fn handle_comment(&mut self, curr: char) {
-        if self.iter.peek().unwrap() == &'/' {
-            loop {
-                match self.iter.next() {
-                    Some('\n') | None => break,
-                    Some(_) => (),
-                }
+        if self.iter.peek() == Some(&'/') {
+            self.bump(); // consume the second '/'
+            let doc = self.iter.peek() == Some(&'/');
+
+            // the line break is left to be lexed, so that the comment's span is on its own line
+            let mut text = String::new();
+            while let Some(c) = self.iter.next_if(|c| *c != '\n') {
+                self.offset += 1;
+                text.push(c);
+            }
+
+            if doc {
+                // the third '/' is part of the text
+                let text = &text[1..];
+                let text = text
+                    .strip_prefix(' ')
+                    .unwrap_or(text)
+                    .trim_end()
+                    .to_string();
+                self.add_token(
+                    TokenKind::DocComment,
+                    format!("///{text}"),
+                    Some(Value::String(text)),
+                );
             }
-            self.line += 1;
         } else {
             self.add_token(TokenKind::Slash, curr.to_string(), None);
         }
     }
 }
+
+/// Whether an identifier can start with `c`
+pub const fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Whether `c` can appear in an identifier after its first character
+pub const fn is_ident_continue(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = Result<Token, Diagnostic<SyntaxError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let c = self.bump()?;
+            self.start = self.offset - 1;
+            self.lex_char(c);
+        }
+
+        let next = self.pending.pop_front()?;
+        if next.is_ok() {
+            self.yielded += 1;
+        }
+        match (next, self.max_tokens) {
+            (Ok(token), Some(max)) if self.yielded > max => {
+                // nothing more is lexed, the rest of the source being too much to hold
+                self.pending.clear();
+                self.iter = "".chars().peekable();
+                Some(Err(diagnostic!(
+                    SyntaxError::ProgramTooLarge {
+                        max,
+                        what: "tokens"
+                    },
+                    token.span
+                )))
+            }
+            (next, _) => Some(next),
+        }
+    }
+}
@@ -14,10 +14,13 @@
 pub mod ast;
 pub mod atium;
 pub mod cli;
+pub mod doctest;
 pub mod environment;
 pub mod error;
+pub mod grammar;
 pub mod impetuous;
 pub mod interpreter;
+pub mod kernel;
 pub mod lexer;
 pub mod parser;
 pub mod reporter;
@@ -1,81 +1,151 @@
-use std::{iter::Peekable, result, vec::IntoIter};
+use std::{collections::HashMap, iter::Peekable, mem, rc::Rc, result, vec::IntoIter};
 
 use color_eyre::Result;
 
-use crate::{dump, error::SyntaxError};
+use crate::{
+    atium::ParseLimits,
+    diagnostic, dump,
+    error::{Span, SyntaxError},
+};
 
 use super::{
-    ast::Stmt,
+    ast::{Arm, Expr, FunDecl, MethodKind, Pattern, Signature, Stmt},
     impetuous::Impetuous,
-    token::{Token, TokenKind},
+    token::{Token, TokenKind, Value},
 };
 
-mod expr;
+pub(crate) mod expr;
 
 pub(super) struct Parser {
     iter: Peekable<IntoIter<Token>>,
     prev: Option<Token>,
+    /// doc comments, keyed by the span of the token that follows them
+    docs: HashMap<Span, String>,
+    /// the number of function bodies being parsed, outside of which `return` is an error
+    functions: usize,
+    /// whether the innermost function being parsed is an `init` method, which cannot return a
+    /// value as calling its class returns the instance
+    initializer: bool,
+    /// the number of class bodies being parsed, outside of which `this` is an error
+    classes: usize,
+    /// errors that do not stop the statement they are in from being parsed, so need no recovery
+    misplaced: Vec<color_eyre::Report>,
+    /// the signatures of the traits declared so far, which the classes implementing them are
+    /// checked against. Traits declared elsewhere, such as in a prelude, are checked at runtime.
+    traits: HashMap<String, Vec<Signature>>,
+    limits: Option<ParseLimits>,
+    /// the number of expressions and statements parsed so far
+    nodes: usize,
+    /// the number of expressions and statements that the one being parsed is nested inside of
+    depth: usize,
+    /// whether the program has exceeded its limits, after which parsing stops
+    too_large: bool,
 }
 
 impl Parser {
     pub fn new(token_stream: Vec<Token>) -> Self {
+        let mut tokens = vec![];
+        let mut docs = HashMap::new();
+        let mut doc: Option<String> = None;
+        for token in token_stream {
+            if token.kind == TokenKind::DocComment {
+                let Some(Value::String(text)) = token.literal else {
+                    unreachable!("doc comments hold their text")
+                };
+                doc = Some(match doc {
+                    Some(doc) => format!("{doc}\n{text}"),
+                    None => text,
+                });
+            } else {
+                if let Some(doc) = doc.take() {
+                    docs.insert(token.span.clone(), doc);
+                }
+                tokens.push(token);
+            }
+        }
+
         Self {
-            iter: token_stream.into_iter().peekable(),
+            iter: tokens.into_iter().peekable(),
             prev: None,
+            docs,
+            functions: 0,
+            initializer: false,
+            classes: 0,
+            misplaced: vec![],
+            traits: HashMap::new(),
+            limits: None,
+            nodes: 0,
+            depth: 0,
+            too_large: false,
         }
     }
 
+    /// Errors once the program exceeds `limits`, rather than parsing it in its entirety
+    #[must_use]
+    pub fn with_limits(mut self, limits: Option<ParseLimits>) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Converts a stream of tokens into an abstract syntax tree
     pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<color_eyre::Report>> {
         let mut statements: Vec<Result<Stmt>> = vec![];
 
-        while self.iter.peek().is_some() {
+        while self.iter.peek().is_some() && !self.too_large {
             statements.push(self.declaration());
         }
 
-        if statements.iter().any(result::Result::is_err) {
-            Err(statements
+        if !self.misplaced.is_empty() || statements.iter().any(result::Result::is_err) {
+            let mut errors: Vec<_> = statements
                 .into_iter()
                 .filter_map(result::Result::err)
-                .collect())
+                .collect();
+            errors.append(&mut self.misplaced);
+            Err(errors)
         } else {
             Ok(statements.into_iter().flatten().collect())
         }
     }
 
     fn declaration(&mut self) -> Result<Stmt> {
+        let declared = match self.peer()?.kind {
+            TokenKind::Var
+            | TokenKind::Fun
+            | TokenKind::At
+            | TokenKind::Class
+            | TokenKind::Trait => self.node(Self::bare_declaration),
+            // statements count themselves towards the limits
+            _ => self.statement(),
+        };
+        declared.inspect_err(|_| {
+            if let Some(prev) = &self.prev() {
+                self.recover(&prev.kind.clone());
+            }
+        })
+    }
+
+    /// Parses a declaration, which [`Parser::declaration`] counts towards the limits
+    fn bare_declaration(&mut self) -> Result<Stmt> {
         match self.peer()?.kind {
             TokenKind::Var => {
                 self.advance()?; // consume Var tok
-                match self.var_decl() {
-                    Ok(stmt) => Ok(stmt),
-                    Err(e) => {
-                        let prev = &self.prev().unwrap().kind.clone();
-                        self.recover(prev);
-                        Err(e)
-                    }
-                }
+                self.var_decl()
             }
-            _ => self.statement().map_err(|e| {
-                if let Some(prev) = &self.prev() {
-                    self.recover(&prev.kind.clone());
-                }
-                e
-            }),
+            TokenKind::Class => self.class_decl(),
+            TokenKind::Trait => self.trait_decl(),
+            _ => self.fun_decl(),
         }
     }
 
     fn var_decl(&mut self) -> Result<Stmt> {
-        let Some(ident) = self.eat(TokenKind::Identifier) else {
-            match self.next() {
-                Some(tok) => dump!(SyntaxError::ExpectedIdent(String::from(&tok.lex()))),
-                None => dump!(SyntaxError::ExpectedIdent(String::from("EOF"))),
-            }
-        };
+        if self.eat(TokenKind::LeftParen).is_some() {
+            return self.var_tuple();
+        }
+        let ident = self.ident()?;
 
         let initial_value = if self.taste(TokenKind::Equal)? {
             self.advance()?; // consume the Equal
-            Some(self.expression().unwrap())
+            Some(self.expression()?)
         } else {
             None
         };
@@ -95,7 +165,312 @@ impl Parser {
         })
     }
 
+    /// Parses the rest of `var (a, b) = value;`, the opening paren having been consumed
+    fn var_tuple(&mut self) -> Result<Stmt> {
+        let mut names = vec![self.ident()?];
+        while self.eat(TokenKind::Comma).is_some() {
+            names.push(self.ident()?);
+        }
+        self.expect(TokenKind::RightParen, ')')?;
+        self.expect(TokenKind::Equal, '=')?;
+        let value = self.expression()?;
+        self.expect(TokenKind::Semicolon, ';')?;
+
+        Ok(Stmt::VarTuple { names, value })
+    }
+
+    /// Parses a function declaration, preceded by any number of `@decorator`s
+    fn fun_decl(&mut self) -> Result<Stmt> {
+        let start = self.peer()?.span;
+        let doc = self.docs.remove(&start);
+        let mut decorators = vec![];
+        while self.eat(TokenKind::At).is_some() {
+            decorators.push(self.expression()?);
+        }
+
+        if self.eat(TokenKind::Fun).is_none() {
+            dump!(SyntaxError::ExpectedDeclaration(
+                self.peer()
+                    .map_or_else(|_| String::from("EOF"), |tok| tok.lex())
+            ))
+        }
+
+        Ok(Stmt::Function {
+            decorators,
+            decl: self.function(doc, false)?,
+        })
+    }
+
+    /// Parses `class name { methods }`, each method being declared like a function without `fun`
+    ///
+    /// The traits the class implements are listed after its name, as in `class name : A, B {}`.
+    fn class_decl(&mut self) -> Result<Stmt> {
+        let start = self.peer()?.span;
+        let doc = self.docs.remove(&start);
+        self.advance()?; // consume Class
+        let name = self.ident()?;
+        let mut traits = vec![];
+        if self.eat(TokenKind::Colon).is_some() {
+            traits.push(self.ident()?);
+            while self.eat(TokenKind::Comma).is_some() {
+                traits.push(self.ident()?);
+            }
+        }
+        self.expect(TokenKind::LeftBrace, '{')?;
+
+        self.classes += 1;
+        let class = self.class_body(name, doc, traits);
+        self.classes -= 1;
+        class
+    }
+
+    /// Parses the methods in the body of a class, up to and including its closing brace
+    ///
+    /// Methods may be declared `static`, or be accessors: `get name()` is called when the property
+    /// `name` is read and `set name(value)` when it is assigned to. `get` and `set` are only
+    /// special when followed by a name, so methods can still be called `get` and `set`.
+    fn class_body(&mut self, name: Token, doc: Option<String>, traits: Vec<Token>) -> Result<Stmt> {
+        let (mut methods, mut statics) = (vec![], vec![]);
+        let (mut getters, mut setters) = (vec![], vec![]);
+
+        while !self.taste(TokenKind::RightBrace)? {
+            let start = self.peer()?.span;
+            let doc = self.docs.remove(&start);
+            if self.eat(TokenKind::Static).is_some() {
+                // static methods have no instance for `this` to refer to
+                let classes = mem::take(&mut self.classes);
+                let method = self.function(doc, false);
+                self.classes = classes;
+                statics.push(method?);
+                continue;
+            }
+
+            let method = self.ident()?;
+            let accessors = match method.lex().as_str() {
+                "get" if self.taste(TokenKind::Identifier)? => Some((&mut getters, 0)),
+                "set" if self.taste(TokenKind::Identifier)? => Some((&mut setters, 1)),
+                _ => None,
+            };
+            match accessors {
+                Some((accessors, params)) => {
+                    let name = self.ident()?;
+                    let decl = self.finish_function(name, doc, false)?;
+                    if decl.arity() != (params..=params) {
+                        self.misplaced.push(
+                            diagnostic!(
+                                SyntaxError::AccessorParameters(decl.name.lex()),
+                                decl.name.span.clone()
+                            )
+                            .into(),
+                        );
+                    }
+                    accessors.push(decl);
+                }
+                None => methods.push(self.finish_function(method, doc, true)?),
+            }
+        }
+        self.expect(TokenKind::RightBrace, '}')?;
+
+        for r#trait in &traits {
+            let unmet = self.traits.get(&r#trait.lex()).and_then(|signatures| {
+                signatures.iter().find(|signature| {
+                    let decls = match signature.kind {
+                        MethodKind::Method => &methods,
+                        MethodKind::Static => &statics,
+                        MethodKind::Getter => &getters,
+                        MethodKind::Setter => &setters,
+                    };
+                    !signature.is_met_by(decls.iter().map(AsRef::as_ref))
+                })
+            });
+            if let Some(signature) = unmet {
+                let error = diagnostic!(
+                    SyntaxError::MissingTraitMethod {
+                        class: name.lex(),
+                        r#trait: r#trait.lex(),
+                        method: signature.to_string(),
+                    },
+                    r#trait.span.clone()
+                )
+                .note(
+                    format!("{} requires {signature}", r#trait.lex()),
+                    signature.name.span.clone(),
+                );
+                self.misplaced.push(error.into());
+            }
+        }
+
+        Ok(Stmt::Class {
+            name,
+            doc,
+            methods,
+            statics,
+            getters,
+            setters,
+            traits,
+        })
+    }
+
+    /// Parses `trait name { signature; ... }`, each signature being a method's name and parameters
+    ///
+    /// Signatures may be declared `static`, `get` or `set` like the methods of a class, which
+    /// must then be declared the same way.
+    fn trait_decl(&mut self) -> Result<Stmt> {
+        let start = self.peer()?.span;
+        let doc = self.docs.remove(&start);
+        self.advance()?; // consume Trait
+        let name = self.ident()?;
+        self.expect(TokenKind::LeftBrace, '{')?;
+
+        let mut methods = vec![];
+        while !self.taste(TokenKind::RightBrace)? {
+            let mut kind = match self.eat(TokenKind::Static) {
+                Some(_) => MethodKind::Static,
+                None => MethodKind::Method,
+            };
+            let mut name = self.ident()?;
+            if kind == MethodKind::Method && self.taste(TokenKind::Identifier)? {
+                match name.lex().as_str() {
+                    "get" => kind = MethodKind::Getter,
+                    "set" => kind = MethodKind::Setter,
+                    _ => (),
+                }
+                name = self.ident()?;
+            }
+            self.expect(TokenKind::LeftParen, '(')?;
+            let mut params = vec![];
+            if !self.taste(TokenKind::RightParen)? {
+                params.push(self.ident()?);
+                while self.eat(TokenKind::Comma).is_some() {
+                    params.push(self.ident()?);
+                }
+            }
+            self.expect(TokenKind::RightParen, ')')?;
+            self.expect(TokenKind::Semicolon, ';')?;
+
+            let accessor = match kind {
+                MethodKind::Getter => Some(0),
+                MethodKind::Setter => Some(1),
+                MethodKind::Method | MethodKind::Static => None,
+            };
+            if accessor.is_some_and(|len| len != params.len()) {
+                self.misplaced.push(
+                    diagnostic!(
+                        SyntaxError::AccessorParameters(name.lex()),
+                        name.span.clone()
+                    )
+                    .into(),
+                );
+            }
+            methods.push(Signature { kind, name, params });
+        }
+        self.expect(TokenKind::RightBrace, '}')?;
+        self.traits.insert(name.lex(), methods.clone());
+
+        Ok(Stmt::Trait { name, doc, methods })
+    }
+
+    /// Parses the name, parameters and body of a function, or of a method if `method` is true
+    fn function(&mut self, doc: Option<String>, method: bool) -> Result<Rc<FunDecl>> {
+        let name = self.ident()?;
+        self.finish_function(name, doc, method)
+    }
+
+    /// Parses the parameters and body of the function called `name`
+    fn finish_function(
+        &mut self,
+        name: Token,
+        doc: Option<String>,
+        method: bool,
+    ) -> Result<Rc<FunDecl>> {
+        self.expect(TokenKind::LeftParen, '(')?;
+        let mut params = vec![];
+        let mut defaults = vec![];
+        let mut rest = None;
+        if !self.taste(TokenKind::RightParen)? {
+            loop {
+                // the rest parameter must be the last, so the parameters end with it
+                if self.eat(TokenKind::Ellipsis).is_some() {
+                    rest = Some(self.ident()?);
+                    break;
+                }
+                let param = self.ident()?;
+                if self.eat(TokenKind::Equal).is_some() {
+                    defaults.push(self.expression()?);
+                } else if !defaults.is_empty() {
+                    // omitted arguments are always the last, so only they can have defaults
+                    dump!(SyntaxError::MissingDefault(param.lex()), param.span)
+                }
+                params.push(param);
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        self.expect(TokenKind::RightParen, ')')?;
+
+        let initializer = method && name.lex() == "init";
+        let enclosing = mem::replace(&mut self.initializer, initializer);
+        self.functions += 1;
+        let body = self.block();
+        self.functions -= 1;
+        self.initializer = enclosing;
+        let body = body?;
+
+        Ok(Rc::new(FunDecl {
+            name,
+            doc,
+            params,
+            defaults,
+            rest,
+            body,
+        }))
+    }
+
+    /// Consumes an identifier
+    fn ident(&mut self) -> Result<Token> {
+        match self.eat(TokenKind::Identifier) {
+            Some(ident) => Ok(ident),
+            None => match self.next() {
+                Some(tok) => dump!(
+                    SyntaxError::ExpectedIdent(String::from(&tok.lex())),
+                    tok.span
+                ),
+                None => dump!(SyntaxError::ExpectedIdent(String::from("EOF"))),
+            },
+        }
+    }
+
+    /// Parses the declarations between a pair of braces
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        let mut stmts = vec![];
+        self.expect(TokenKind::LeftBrace, '{')?;
+
+        while matches!(self.taste(TokenKind::RightBrace), Ok(false)) {
+            stmts.push(self.declaration()?);
+        }
+
+        match self.step() {
+            Some(tok) => match tok.kind {
+                TokenKind::RightBrace => Ok(stmts),
+                _ => dump!(SyntaxError::ExpectedCharacter {
+                    expected: '}',
+                    found: tok.lex()
+                }),
+            },
+            None => dump!(SyntaxError::ExpectedCharacter {
+                expected: '}',
+                found: String::from("EOF")
+            }),
+        }
+    }
+
     fn statement(&mut self) -> Result<Stmt> {
+        self.node(Self::bare_statement)
+    }
+
+    /// Parses a statement, which [`Parser::statement`] counts towards the limits
+    fn bare_statement(&mut self) -> Result<Stmt> {
         match self.peer()?.kind {
             TokenKind::Print => {
                 self.eat(TokenKind::Print).unwrap();
@@ -117,29 +492,348 @@ impl Parser {
                     }),
                 }
             }
-            TokenKind::LeftBrace => {
-                let mut stmts = vec![];
-                self.eat(TokenKind::LeftBrace).unwrap();
+            TokenKind::LeftBrace => Ok(Stmt::Block(self.block()?)),
+            TokenKind::Defer => {
+                self.advance()?; // consume Defer
+                let expr = self.expression()?;
+                self.expect(TokenKind::Semicolon, ';')?;
 
-                while let Ok(false) = self.taste(TokenKind::RightBrace) {
-                    stmts.push(self.declaration()?)
+                Ok(Stmt::Defer(expr))
+            }
+            TokenKind::With => {
+                let keyword = self.advance()?;
+                self.expect(TokenKind::LeftParen, '(')?;
+                if self.eat(TokenKind::Var).is_none() {
+                    dump!(SyntaxError::ExpectedDeclaration(
+                        self.peer()
+                            .map_or_else(|_| String::from("EOF"), |tok| tok.lex())
+                    ))
                 }
+                let name = self.ident()?;
+                self.expect(TokenKind::Equal, '=')?;
+                let value = self.expression()?;
+                self.expect(TokenKind::RightParen, ')')?;
 
-                match self.step() {
-                    Some(tok) => match tok.kind {
-                        TokenKind::RightBrace => Ok(Stmt::Block(stmts)),
-                        _ => dump!(SyntaxError::ExpectedCharacter {
-                            expected: '}',
-                            found: tok.lex()
-                        }),
-                    },
-                    None => dump!(SyntaxError::ExpectedCharacter {
-                        expected: '}',
-                        found: String::from("EOF")
-                    }),
+                Ok(Stmt::With {
+                    keyword,
+                    name,
+                    value,
+                    body: self.block()?,
+                })
+            }
+            TokenKind::While => {
+                let keyword = self.advance()?;
+                self.expect(TokenKind::LeftParen, '(')?;
+                let condition = self.expression()?;
+                self.expect(TokenKind::RightParen, ')')?;
+
+                Ok(Stmt::While {
+                    keyword,
+                    condition,
+                    body: Box::new(self.statement()?),
+                })
+            }
+            TokenKind::Do => {
+                self.advance()?;
+                let body = Box::new(self.statement()?);
+                let keyword = self.expect_keyword(TokenKind::While, "while")?;
+                self.expect(TokenKind::LeftParen, '(')?;
+                let condition = self.expression()?;
+                self.expect(TokenKind::RightParen, ')')?;
+                self.expect(TokenKind::Semicolon, ';')?;
+
+                Ok(Stmt::DoWhile {
+                    body,
+                    keyword,
+                    condition,
+                })
+            }
+            TokenKind::For => self.for_loop(),
+            TokenKind::Match => {
+                let keyword = self.advance()?;
+                let value = self.expression()?;
+                self.expect(TokenKind::LeftBrace, '{')?;
+
+                let mut arms = vec![];
+                while !self.taste(TokenKind::RightBrace)? {
+                    let pattern = self.pattern()?;
+                    self.expect_keyword(TokenKind::FatArrow, "=>")?;
+                    let body = self.statement()?;
+                    self.eat(TokenKind::Comma);
+
+                    arms.push(Arm { pattern, body });
                 }
+                self.expect(TokenKind::RightBrace, '}')?;
+
+                Ok(Stmt::Match {
+                    keyword,
+                    value,
+                    arms,
+                })
             }
-            _ => Ok(Stmt::Expr(self.expression()?)),
+            TokenKind::Throw => {
+                let keyword = self.advance()?;
+                let value = self.expression()?;
+                self.expect(TokenKind::Semicolon, ';')?;
+
+                Ok(Stmt::Throw { keyword, value })
+            }
+            TokenKind::Try => {
+                self.advance()?; // consume Try
+                let body = self.block()?;
+                self.expect_keyword(TokenKind::Catch, "catch")?;
+                self.expect(TokenKind::LeftParen, '(')?;
+                let name = self.ident()?;
+                self.expect(TokenKind::RightParen, ')')?;
+
+                Ok(Stmt::Try {
+                    body,
+                    name,
+                    handler: self.block()?,
+                })
+            }
+            TokenKind::Return => {
+                let keyword = self.advance()?;
+                let value = if self.taste(TokenKind::Semicolon)? {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                self.expect(TokenKind::Semicolon, ';')?;
+
+                if self.functions == 0 {
+                    self.misplaced.push(
+                        diagnostic!(SyntaxError::ReturnOutsideFunction, keyword.span.clone())
+                            .into(),
+                    );
+                } else if self.initializer && value.is_some() {
+                    self.misplaced.push(
+                        diagnostic!(SyntaxError::ReturnFromInit, keyword.span.clone()).into(),
+                    );
+                }
+                Ok(Stmt::Return { keyword, value })
+            }
+            _ => {
+                let expr = self.expression()?;
+
+                // the final expression of a program may leave out its semicolon, which lets
+                // snippets such as `eval("1 + 2")` be written naturally
+                if self.eat(TokenKind::Semicolon).is_none() && self.iter.peek().is_some() {
+                    dump!(SyntaxError::ExpectedCharacter {
+                        expected: ';',
+                        found: self
+                            .peer()
+                            .map_or_else(|_| String::from("EOF"), |tok| tok.lex()),
+                    })
+                }
+                Ok(Stmt::Expr(expr))
+            }
+        }
+    }
+
+    /// Parses the pattern of an arm of a `match`: a literal, possibly negated if it is a number,
+    /// `_`, or one of the string patterns `"prefix" ...rest`, `...rest "suffix"` and
+    /// `regex "pattern"`
+    fn pattern(&mut self) -> Result<Pattern> {
+        let tok = self.advance()?;
+        match tok.kind {
+            TokenKind::String if self.eat(TokenKind::Ellipsis).is_some() => Ok(Pattern::Prefix {
+                prefix: tok,
+                rest: self.ident()?,
+            }),
+            TokenKind::Ellipsis => Ok(Pattern::Suffix {
+                rest: self.ident()?,
+                suffix: self.string_pattern()?,
+            }),
+            TokenKind::Identifier if tok.lex() == "regex" => {
+                let pattern = self.string_pattern()?;
+                Self::regex(&pattern)?;
+                Ok(Pattern::Regex {
+                    keyword: tok,
+                    pattern,
+                })
+            }
+            TokenKind::Number
+            | TokenKind::String
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Nil => Ok(Pattern::Literal(tok)),
+            TokenKind::Identifier if tok.lex() == "_" => Ok(Pattern::Wildcard(tok)),
+            TokenKind::Minus if self.taste(TokenKind::Number)? => {
+                let number = self.advance()?;
+                let literal = match number.literal {
+                    Some(Value::Integer(int)) => Value::Integer(-int),
+                    Some(Value::Float(float)) => Value::Float(-float),
+                    #[cfg(feature = "decimal")]
+                    Some(Value::Decimal(decimal)) => Value::Decimal(-decimal),
+                    #[cfg(feature = "units")]
+                    Some(Value::Quantity(quantity)) => Value::Quantity(-quantity),
+                    _ => unreachable!("number tokens hold a number"),
+                };
+
+                let mut span = tok.span;
+                span.lex = format!("-{}", number.lex());
+                Ok(Pattern::Literal(Token::new(
+                    TokenKind::Number,
+                    Some(literal),
+                    span,
+                )))
+            }
+            _ => dump!(SyntaxError::ExpectedPattern(tok.lex()), tok.span),
+        }
+    }
+
+    /// Parses the string that a string pattern is made of
+    fn string_pattern(&mut self) -> Result<Token> {
+        let tok = self.advance()?;
+        match tok.kind {
+            TokenKind::String => Ok(tok),
+            _ => dump!(SyntaxError::ExpectedPattern(tok.lex()), tok.span),
+        }
+    }
+
+    /// Checks that the string `pattern` is a valid regular expression, so that `match` can
+    /// rely on it being one
+    #[cfg(feature = "regex")]
+    fn regex(pattern: &Token) -> Result<()> {
+        let Some(Value::String(regex)) = &pattern.literal else {
+            unreachable!("string tokens hold a string")
+        };
+        match regex::Regex::new(regex) {
+            Ok(_) => Ok(()),
+            Err(err) => dump!(
+                SyntaxError::InvalidRegex(pattern.lex(), err.to_string()),
+                pattern.span.clone()
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn regex(pattern: &Token) -> Result<()> {
+        dump!(
+            SyntaxError::RegexDisabled(pattern.lex()),
+            pattern.span.clone()
+        )
+    }
+
+    /// Parses a `for` loop, desugaring it into a `while` loop
+    ///
+    /// `for (init; condition; increment) body` becomes a block running `init` followed by
+    /// `while (condition) { body increment; }`, where a missing condition is `true`.
+    ///
+    /// `for name in iterable { body }` is kept as is, iterating being left to the interpreter.
+    fn for_loop(&mut self) -> Result<Stmt> {
+        let keyword = self.advance()?;
+        if let Some(name) = self.eat(TokenKind::Identifier) {
+            self.expect_keyword(TokenKind::In, "in")?;
+            return Ok(Stmt::ForIn {
+                keyword,
+                name,
+                iterable: self.expression()?,
+                body: self.block()?,
+            });
+        }
+        self.expect(TokenKind::LeftParen, '(')?;
+
+        let init = if self.eat(TokenKind::Semicolon).is_some() {
+            None
+        } else if self.eat(TokenKind::Var).is_some() {
+            Some(self.var_decl()?)
+        } else {
+            let expr = self.expression()?;
+            self.expect(TokenKind::Semicolon, ';')?;
+            Some(Stmt::Expr(expr))
+        };
+
+        let condition = if self.taste(TokenKind::Semicolon)? {
+            let mut span = keyword.span.clone();
+            span.lex = String::from("true");
+            Expr::Literal(Token::new(
+                TokenKind::True,
+                Some(Value::Boolean(true)),
+                span,
+            ))
+        } else {
+            self.expression()?
+        };
+        self.expect(TokenKind::Semicolon, ';')?;
+
+        let increment = if self.taste(TokenKind::RightParen)? {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.expect(TokenKind::RightParen, ')')?;
+
+        let mut body = self.statement()?;
+        if let Some(increment) = increment {
+            body = Stmt::Block(vec![body, Stmt::Expr(increment)]);
+        }
+        let body = Stmt::While {
+            keyword,
+            condition,
+            body: Box::new(body),
+        };
+
+        Ok(match init {
+            Some(init) => Stmt::Block(vec![init, body]),
+            None => body,
+        })
+    }
+
+    /// Parses a node of the syntax tree with `parse`, erroring instead if it would take the
+    /// program over its limits
+    fn node<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.nodes += 1;
+        self.depth += 1;
+        let node = self.check_limits().and_then(|()| parse(self));
+        self.depth -= 1;
+        node
+    }
+
+    fn check_limits(&mut self) -> Result<()> {
+        let Some(limits) = self.limits else {
+            return Ok(());
+        };
+        let (max, what) = if self.nodes > limits.nodes {
+            (limits.nodes, "syntax tree nodes")
+        } else if self.depth > limits.depth {
+            (limits.depth, "levels of nesting")
+        } else {
+            return Ok(());
+        };
+
+        self.too_large = true;
+        let kind = SyntaxError::ProgramTooLarge { max, what };
+        match self.peer() {
+            Ok(tok) => dump!(kind, tok.span),
+            Err(_) => dump!(kind),
+        }
+    }
+
+    /// Consumes the next token, erroring if it is not of the `expected` kind
+    fn expect(&mut self, expected: TokenKind, lex: char) -> Result<Token> {
+        match self.eat(expected) {
+            Some(tok) => Ok(tok),
+            None => dump!(SyntaxError::ExpectedCharacter {
+                expected: lex,
+                found: self
+                    .peer()
+                    .map_or_else(|_| String::from("EOF"), |tok| tok.lex()),
+            }),
+        }
+    }
+
+    /// Like [`Parser::expect`], but for tokens longer than a single character
+    fn expect_keyword(&mut self, expected: TokenKind, lex: &'static str) -> Result<Token> {
+        match self.eat(expected) {
+            Some(tok) => Ok(tok),
+            None => dump!(SyntaxError::ExpectedKeyword {
+                expected: lex,
+                found: self
+                    .peer()
+                    .map_or_else(|_| String::from("EOF"), |tok| tok.lex()),
+            }),
         }
     }
 
@@ -158,12 +852,19 @@ impl Parser {
         for next in self.iter.by_ref() {
             match next.kind {
                 TokenKind::Class
+                | TokenKind::Trait
                 | TokenKind::Fun
                 | TokenKind::Var
                 | TokenKind::For
                 | TokenKind::If
                 | TokenKind::While
+                | TokenKind::Do
                 | TokenKind::Print
+                | TokenKind::Defer
+                | TokenKind::With
+                | TokenKind::Try
+                | TokenKind::Throw
+                | TokenKind::Match
                 | TokenKind::Return => {
                     return Some(());
                 }
@@ -180,8 +881,9 @@ impl Impetuous for Parser {
     /// Replaces the `next` function on Iterators, advancing one step forwards while keeping track
     /// of the previous elem
     fn step(&mut self) -> Option<Token> {
-        self.prev = self.iter.clone().next();
-        self.iter.next()
+        let next = self.iter.next();
+        self.prev.clone_from(&next);
+        next
     }
 
     /// Access the element returned last
@@ -191,13 +893,15 @@ impl Impetuous for Parser {
 
     /// Advance the iterator, erroring if EOF occurs prematurely
     fn advance(&mut self) -> Result<Token> {
-        self.step().ok_or(SyntaxError::UnexpectedEOF.into())
+        self.step().ok_or_else(|| SyntaxError::UnexpectedEOF.into())
     }
 
     /// Peek the iterator, erroring if EOF occurs early
     fn peer(&mut self) -> Result<Token> {
-        let mut iter = self.iter.clone();
-        iter.next().ok_or(SyntaxError::UnexpectedEOF.into())
+        self.iter
+            .peek()
+            .cloned()
+            .ok_or_else(|| SyntaxError::UnexpectedEOF.into())
     }
 
     /// Consumes the next item, verifing that it is the right value
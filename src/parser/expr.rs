@@ -1,9 +1,14 @@
-use crate::dump;
 use crate::error::RuntimeError;
+use crate::{diagnostic, dump};
 use color_eyre::Result;
 
 use super::Parser;
-use crate::{ast::Expr, error::SyntaxError, impetuous::Impetuous, token::TokenKind};
+use crate::{
+    ast::{Expr, Quoted, Stmt},
+    error::SyntaxError,
+    impetuous::Impetuous,
+    token::{Token, TokenKind},
+};
 
 impl Parser {
     pub fn expression(&mut self) -> Result<Expr> {
@@ -11,41 +16,47 @@ impl Parser {
     }
 
     fn expr(&mut self, min_bp: u8) -> Result<Expr> {
-        let mut left = match self.peer()?.kind {
-            TokenKind::Number | TokenKind::String | TokenKind::True | TokenKind::False => {
-                Expr::Literal(self.advance()?)
-            }
-            TokenKind::Identifier => Expr::Variable(self.advance()?), // NOTE variables are not
-            // only one character
-            TokenKind::LeftParen => {
-                self.advance()?; // consume LeftParen
-                let inner = self.expr(0)?;
-
-                if self.peer()?.kind != TokenKind::RightParen {
-                    return Err(SyntaxError::ExpectedCharacter {
-                        expected: ')',
-                        found: self.advance()?.span.lex,
-                    }
-                    .into());
-                }
-                self.advance()?; // consume RightParen
+        self.node(|parser| parser.bare_expr(min_bp))
+    }
 
-                Expr::Grouping(Box::new(inner))
-            }
+    /// Parses an expression, which [`Parser::expr`] counts towards the limits
+    fn bare_expr(&mut self, min_bp: u8) -> Result<Expr> {
+        let mut left = match self.peer()?.kind {
+            TokenKind::Number
+            | TokenKind::String
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Nil => Expr::Literal(self.advance()?),
+            TokenKind::Identifier => self.variable()?,
+            TokenKind::This => self.this()?,
+            TokenKind::LeftParen => self.grouping()?,
             TokenKind::Minus | TokenKind::Bang => {
                 let op = self.advance()?;
-                let (_, r_bp) = prefix_bp(&op.kind);
-                let right = self.expr(r_bp)?;
+                let right = self.expr(op.kind.prefix_bp().unwrap())?;
                 Expr::Unary(op, Box::new(right))
             }
-            x => {
-                dbg!(&self.iter);
-                unimplemented!("{x:?}")
+            TokenKind::Quote => {
+                self.advance()?; // consume Quote
+                Expr::Quote(Box::new(self.quoted()?))
             }
+            TokenKind::Unquote => {
+                let keyword = self.advance()?;
+                self.expect(TokenKind::LeftParen, '(')?;
+                let inner = self.expr(0)?;
+                self.expect(TokenKind::RightParen, ')')?;
+
+                Expr::Unquote(keyword, Box::new(inner))
+            }
+            _ => return self.expected_expression(),
         };
 
         while let Some(op) = self.iter.peek() {
-            if let Some((l_bp, r_bp)) = infix_bp(&op.kind) {
+            if let Some(l_bp) = op.kind.postfix_bp() {
+                if l_bp < min_bp {
+                    break;
+                }
+                left = self.postfix(left)?;
+            } else if let Some((l_bp, r_bp)) = op.kind.infix_bp() {
                 if l_bp < min_bp {
                     break;
                 }
@@ -53,16 +64,7 @@ impl Parser {
                 let op = self.advance()?; // consume operator
                 let right = self.expr(r_bp)?;
 
-                left = match op.kind {
-                    TokenKind::Equal => {
-                        if let Expr::Variable(name) = left {
-                            Expr::Assignment(name, Box::new(right))
-                        } else {
-                            dump!(RuntimeError::InvalidAssignmentTarget::<String>)
-                        }
-                    }
-                    _ => Expr::Binary(Box::new(left), op, Box::new(right)),
-                };
+                left = infix(left, op, right)?;
             } else {
                 break;
             }
@@ -70,25 +72,238 @@ impl Parser {
 
         Ok(left)
     }
+
+    /// Errors at the token that was found where an expression should have started
+    ///
+    /// Kept out of [`Parser::bare_expr`], whose frame is on the stack once per level of nesting.
+    fn expected_expression(&mut self) -> Result<Expr> {
+        let tok = self.advance()?;
+        dump!(SyntaxError::ExpectedExpression(tok.lex()), tok.span)
+    }
+
+    /// Parses a variable, or a set literal if it is `set` directly followed by a brace
+    ///
+    /// `set` is not a keyword, so `for x in set { ... }` still iterates over a variable.
+    fn variable(&mut self) -> Result<Expr> {
+        let name = self.advance()?;
+        let adjoined = self.iter.peek().is_some_and(|brace| {
+            brace.kind == TokenKind::LeftBrace && brace.span.start == name.span.end
+        });
+        if name.lex() != "set" || !adjoined {
+            return Ok(Expr::Variable(name));
+        }
+
+        self.advance()?; // consume LeftBrace
+        let mut elements = vec![];
+        while !self.taste(TokenKind::RightBrace)? {
+            elements.push(self.expr(0)?);
+            if self.eat(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        self.expect(TokenKind::RightBrace, '}')?;
+
+        Ok(Expr::SetLiteral(name, elements))
+    }
+
+    /// Parses an expression in parentheses, or a tuple if there are commas between several
+    fn grouping(&mut self) -> Result<Expr> {
+        let paren = self.advance()?; // consume LeftParen
+        let inner = self.expr(0)?;
+        if self
+            .iter
+            .peek()
+            .is_some_and(|tok| tok.kind == TokenKind::Comma)
+        {
+            return self.tuple(paren, inner);
+        }
+        self.close_paren()?;
+
+        Ok(Expr::Grouping(Box::new(inner)))
+    }
+
+    /// Parses the rest of a tuple after its first element
+    ///
+    /// Kept out of [`Parser::grouping`], whose frame is on the stack once per level of nesting.
+    fn tuple(&mut self, paren: Token, first: Expr) -> Result<Expr> {
+        let mut elements = vec![first];
+        while self.eat(TokenKind::Comma).is_some() {
+            elements.push(self.expr(0)?);
+        }
+        self.close_paren()?;
+
+        Ok(Expr::Tuple(paren, elements))
+    }
+
+    fn close_paren(&mut self) -> Result<()> {
+        if self.peer()?.kind != TokenKind::RightParen {
+            return Err(SyntaxError::ExpectedCharacter {
+                expected: ')',
+                found: self.advance()?.span.lex,
+            }
+            .into());
+        }
+        self.advance()?; // consume RightParen
+        Ok(())
+    }
+
+    /// Parses `this`, which only refers to something inside of a method
+    fn this(&mut self) -> Result<Expr> {
+        let keyword = self.advance()?;
+        if self.classes == 0 {
+            self.misplaced
+                .push(diagnostic!(SyntaxError::ThisOutsideClass, keyword.span.clone()).into());
+        }
+        Ok(Expr::This(keyword))
+    }
+
+    /// Parses the braces following `quote`
+    ///
+    /// If they contain a single expression without a trailing semicolon then that expression is
+    /// quoted, otherwise the statements within are.
+    fn quoted(&mut self) -> Result<Quoted> {
+        self.expect(TokenKind::LeftBrace, '{')?;
+
+        let mut stmts = vec![];
+        if !starts_statement(&self.peer()?.kind) && !self.taste(TokenKind::RightBrace)? {
+            let expr = self.expression()?;
+            if self.eat(TokenKind::RightBrace).is_some() {
+                return Ok(Quoted::Expr(expr));
+            }
+            self.expect(TokenKind::Semicolon, ';')?;
+            stmts.push(Stmt::Expr(expr));
+        }
+
+        while !self.taste(TokenKind::RightBrace)? {
+            stmts.push(self.declaration()?);
+        }
+        self.expect(TokenKind::RightBrace, '}')?;
+
+        Ok(Quoted::Stmts(stmts))
+    }
+
+    /// Parses the postfix operator that follows `left`: a call, property access or index
+    fn postfix(&mut self, left: Expr) -> Result<Expr> {
+        match self.peer()?.kind {
+            TokenKind::LeftParen => self.finish_call(left),
+            TokenKind::Dot => {
+                self.advance()?; // consume Dot
+                Ok(Expr::Get(Box::new(left), self.ident()?))
+            }
+            TokenKind::LeftBracket => {
+                self.advance()?; // consume LeftBracket
+                let index = self.expr(0)?;
+                let bracket = self.expect(TokenKind::RightBracket, ']')?;
+
+                Ok(Expr::Index(Box::new(left), bracket, Box::new(index)))
+            }
+            x => unreachable!("{x:?} is not a postfix operator"),
+        }
+    }
+
+    /// Parses the argument list of a call to `callee`
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr> {
+        self.advance()?; // consume LeftParen
+
+        let mut args = vec![];
+        let mut named = vec![];
+        if !self.taste(TokenKind::RightParen)? {
+            loop {
+                let arg = self.expr(0)?;
+                // `name: value`, whose name parses as a variable before the colon is seen
+                match arg {
+                    Expr::Variable(name) if self.eat(TokenKind::Colon).is_some() => {
+                        named.push((name, self.expr(0)?));
+                    }
+                    arg if !named.is_empty() => {
+                        let error = SyntaxError::PositionalAfterNamed(arg.to_string());
+                        match arg.first_token() {
+                            Some(start) => dump!(error, start.span.clone()),
+                            None => dump!(error),
+                        }
+                    }
+                    arg => args.push(arg),
+                }
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+
+        let Some(paren) = self.eat(TokenKind::RightParen) else {
+            dump!(SyntaxError::ExpectedCharacter {
+                expected: ')',
+                found: self
+                    .peer()
+                    .map_or_else(|_| String::from("EOF"), |tok| tok.lex()),
+            })
+        };
+        Ok(Expr::Call(Box::new(callee), paren, args, named))
+    }
 }
 
-/// Returns the binding power for an infix operator
-fn infix_bp(op: &TokenKind) -> Option<(u8, u8)> {
-    let bp = match op {
-        TokenKind::Equal => (2, 1),
-        TokenKind::EqualEqual => (4, 3),
-        TokenKind::Plus | TokenKind::Minus => (5, 6),
-        TokenKind::Star | TokenKind::Slash => (7, 8),
-        _ => return None,
-    };
-
-    Some(bp)
+/// Combines the operands of the infix operator `op`
+///
+/// Kept out of [`Parser::bare_expr`], whose frame is on the stack once per level of nesting.
+fn infix(left: Expr, op: Token, right: Expr) -> Result<Expr> {
+    Ok(match op.kind {
+        TokenKind::PipeGreater => pipe(left, op, right),
+        TokenKind::And | TokenKind::Or => Expr::Logical(Box::new(left), op, Box::new(right)),
+        TokenKind::Equal => match left {
+            Expr::Variable(name) => Expr::Assignment(name, Box::new(right)),
+            Expr::Get(object, name) => Expr::Set(object, name, Box::new(right)),
+            Expr::Tuple(_, elements) => Expr::Destructure(targets(elements)?, Box::new(right)),
+            _ => dump!(RuntimeError::InvalidAssignmentTarget::<String>),
+        },
+        _ => Expr::Binary(Box::new(left), op, Box::new(right)),
+    })
 }
 
-/// Returns the binding power of a prefix operator
-fn prefix_bp(op: &TokenKind) -> ((), u8) {
-    match op {
-        TokenKind::Minus | TokenKind::Bang => ((), 7),
-        _ => panic!("bad op: {:?}", op),
+/// The variables that the elements of a tuple being assigned to name
+fn targets(elements: Vec<Expr>) -> Result<Vec<Token>> {
+    elements
+        .into_iter()
+        .map(|element| match element {
+            Expr::Variable(name) => Ok(name),
+            _ => dump!(RuntimeError::InvalidAssignmentTarget::<String>),
+        })
+        .collect()
+}
+
+/// Rewrites `value |> callee` into a call, `value` becoming the first argument
+///
+/// When `callee` is itself a call, as in `x |> g(2)`, `value` is placed before its existing
+/// arguments, giving `g(x, 2)`. Otherwise `callee` is called with `value` alone.
+fn pipe(value: Expr, op: Token, callee: Expr) -> Expr {
+    match callee {
+        Expr::Call(callee, paren, mut args, named) => {
+            args.insert(0, value);
+            Expr::Call(callee, paren, args, named)
+        }
+        callee => Expr::Call(Box::new(callee), op, vec![value], vec![]),
     }
 }
+
+/// Whether a token begins a statement that is not an expression statement
+fn starts_statement(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Var
+            | TokenKind::Print
+            | TokenKind::LeftBrace
+            | TokenKind::Class
+            | TokenKind::Trait
+            | TokenKind::Fun
+            | TokenKind::At
+            | TokenKind::For
+            | TokenKind::If
+            | TokenKind::While
+            | TokenKind::Do
+            | TokenKind::Return
+            | TokenKind::Defer
+            | TokenKind::With
+            | TokenKind::Try
+            | TokenKind::Throw
+            | TokenKind::Match
+    )
+}
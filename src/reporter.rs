@@ -0,0 +1,66 @@
+//! Renders errors along with the source code that caused them
+//!
+//! ```text
+//! error: division by zero
+//!     --> repl:1:1:9
+//!      |
+//!    1 | print 1 / 0;
+//!      |         ^
+//! ```
+use color_eyre::Report;
+use colored::Colorize;
+use std::fmt;
+
+use crate::error::{
+    describe, styled,
+    trace::{Frames, Traced},
+    PathStyle, Span,
+};
+
+/// Renders `error`, underlining the source code where it occurred and showing the paths of files
+/// in `style`
+pub fn render<'a>(error: &'a Report, style: &'a PathStyle) -> impl fmt::Display + 'a {
+    Rendered(error, style)
+}
+
+struct Rendered<'a>(&'a Report, &'a PathStyle);
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the snippet belongs beneath the error itself, before the calls that led to it
+        let (error, frames) = self
+            .0
+            .downcast_ref::<Traced>()
+            .map_or((self.0, None), |traced| {
+                (&traced.error, Some(Frames(&traced.frames, self.1)))
+            });
+
+        write!(f, "{}", styled(error, self.1))?;
+        if let Some(span) = describe(error).span {
+            snippet(f, &span)?;
+        }
+        if let Some(frames) = frames {
+            write!(f, "{frames}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes out the line that `span` is on, with the text of the span underlined
+fn snippet(f: &mut fmt::Formatter<'_>, span: &Span) -> fmt::Result {
+    let Some(file) = &span.file else {
+        return Ok(());
+    };
+    let line = span.line.0 as usize;
+    let text = file.text();
+    let Some(text) = line.checked_sub(1).and_then(|idx| text.lines().nth(idx)) else {
+        return Ok(());
+    };
+
+    let bar = "|".blue().bold();
+    let padding = " ".repeat(span.column.0.saturating_sub(1) as usize);
+    let underline = "^".repeat(span.lex.chars().count().max(1));
+    write!(f, "\n     {bar}")?;
+    write!(f, "\n{} {bar} {text}", format!("{line:>4}").blue().bold())?;
+    write!(f, "\n     {bar} {padding}{}", underline.red().bold())
+}
@@ -6,9 +6,13 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-pub use self::{r#type::Type, value::Value};
+pub use self::{map::Map, r#type::Type, set::Set, value::Value};
 use crate::error::Span;
 
+pub mod map;
+#[cfg(feature = "units")]
+pub mod quantity;
+pub mod set;
 pub mod r#type;
 pub mod value;
 
@@ -66,13 +70,19 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
     Plus,
     Semicolon,
     Slash,
     Star,
+    At,
+    Bar,
+    Ampersand,
 
     // One or two character tokens.
     Bang,
@@ -83,27 +93,137 @@ pub enum TokenKind {
     GreaterEqual,
     Less,
     LessEqual,
+    PipeGreater,
+    FatArrow,
+    Ellipsis,
 
     // Literals.
     Identifier,
     String,
     Number,
 
+    /// A `///` comment, documenting the declaration that follows it
+    DocComment,
+
     // Keywords.
     And,
+    Catch,
     Class,
+    Defer,
+    Do,
     Else,
     False,
     Fun,
     For,
     If,
+    In,
+    Is,
+    Match,
     Nil,
     Or,
     Print,
+    Quote,
     Return,
+    Static,
     Super,
     This,
+    Throw,
+    Trait,
     True,
+    Try,
+    Unquote,
     Var,
     While,
+    With,
+}
+
+impl TokenKind {
+    /// Every keyword, along with the kind of token that it is lexed as
+    pub const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("and", Self::And),
+        ("catch", Self::Catch),
+        ("class", Self::Class),
+        ("defer", Self::Defer),
+        ("do", Self::Do),
+        ("else", Self::Else),
+        ("false", Self::False),
+        ("fun", Self::Fun),
+        ("for", Self::For),
+        ("if", Self::If),
+        ("in", Self::In),
+        ("is", Self::Is),
+        ("match", Self::Match),
+        ("nil", Self::Nil),
+        ("or", Self::Or),
+        ("print", Self::Print),
+        ("quote", Self::Quote),
+        ("return", Self::Return),
+        ("static", Self::Static),
+        ("super", Self::Super),
+        ("this", Self::This),
+        ("throw", Self::Throw),
+        ("trait", Self::Trait),
+        ("true", Self::True),
+        ("try", Self::Try),
+        ("unquote", Self::Unquote),
+        ("var", Self::Var),
+        ("while", Self::While),
+        ("with", Self::With),
+    ];
+
+    /// The keyword spelt `lex`, if there is one
+    pub fn keyword(lex: &str) -> Option<Self> {
+        Self::KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == lex)
+            .map(|(_, kind)| kind.clone())
+    }
+
+    /// Whether the token is one of [`TokenKind::KEYWORDS`]
+    pub fn is_keyword(&self) -> bool {
+        Self::KEYWORDS.iter().any(|(_, kind)| kind == self)
+    }
+
+    /// Whether the token is a prefix or infix operator. Calls, property accesses and indexing are
+    /// parsed as postfix operators but are not considered to be operators themselves.
+    pub fn is_operator(&self) -> bool {
+        self.prefix_bp().is_some() || self.infix_bp().is_some()
+    }
+
+    /// Returns the binding power of a postfix operator, all of which bind tighter than any prefix
+    /// or infix operator
+    pub const fn postfix_bp(&self) -> Option<u8> {
+        match self {
+            Self::LeftParen | Self::Dot | Self::LeftBracket => Some(19),
+            _ => None,
+        }
+    }
+
+    /// Returns the left and right binding powers of an infix operator
+    pub const fn infix_bp(&self) -> Option<(u8, u8)> {
+        let bp = match self {
+            Self::Equal => (2, 1),
+            Self::Or => (3, 4),
+            Self::And => (5, 6),
+            Self::PipeGreater => (7, 8),
+            Self::EqualEqual | Self::BangEqual => (9, 10),
+            Self::Greater | Self::GreaterEqual | Self::Less | Self::LessEqual | Self::Is => {
+                (11, 12)
+            }
+            // union and intersection bind as addition and multiplication do
+            Self::Plus | Self::Minus | Self::Bar => (13, 14),
+            Self::Star | Self::Slash | Self::Ampersand => (15, 16),
+            _ => return None,
+        };
+
+        Some(bp)
+    }
+
+    /// Returns the binding power of a prefix operator
+    pub const fn prefix_bp(&self) -> Option<u8> {
+        match self {
+            Self::Minus | Self::Bang => Some(15),
+            _ => None,
+        }
+    }
 }
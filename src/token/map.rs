@@ -0,0 +1,130 @@
+use indexmap::IndexMap;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+};
+
+use super::value::Value;
+
+/// A mapping between values, which remembers the order that its keys were inserted in
+///
+/// Keys are the same when `==` says they are: every NaN is the same key, as are `0.0` and `-0.0`,
+/// and a float with no fractional part is the same key as the integer it equals, so `1` and
+/// `1.0` are one key. A key keeps the value it was first inserted as, so `-0.0` stays a float
+/// even though `0` finds it. Decimal keys are only ever the same as other decimals.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Map(IndexMap<Key, Value>);
+
+/// The keys of a map, in the order that they were inserted
+pub type IntoKeys = iter::Map<indexmap::map::IntoKeys<Key, Value>, fn(Key) -> Value>;
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.0.get(&Key(key.clone()))
+    }
+
+    /// Inserts `value` at `key`, returning the value previously there. A key equal to `key` that
+    /// is already present is kept as it is.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        self.0.insert(Key(key), value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.0.iter().map(|(key, value)| (&key.0, value))
+    }
+
+    pub fn into_keys(self) -> IntoKeys {
+        self.0.into_keys().map(|key| key.0)
+    }
+}
+
+impl FromIterator<(Value, Value)> for Map {
+    fn from_iter<T: IntoIterator<Item = (Value, Value)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (Key(key), value))
+                .collect(),
+        )
+    }
+}
+
+/// A key of a [`Map`] or element of a [`Set`](super::Set), which is kept as it was inserted but
+/// is hashed and compared as the representative of the keys equal to it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct Key(pub(super) Value);
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        canonical(&self.0) == canonical(&other.0)
+    }
+}
+
+impl Eq for Key {}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        canonical(&self.0).hash(state);
+    }
+}
+
+/// The representative of all keys that are equal to `key`
+fn canonical(key: &Value) -> Cow<'_, Value> {
+    /// 2^127, the smallest float too large to be an integer
+    const INTEGERS: f64 = 1.701_411_834_604_692_3e38;
+
+    match key {
+        Value::Float(float) if float.is_nan() => Cow::Owned(Value::Float(OrderedFloat(f64::NAN))),
+        // `-0.0` has no fractional part, so is `0` along with `0.0`
+        #[allow(clippy::cast_possible_truncation)] // it is whole and in range
+        Value::Float(float) if float.fract() == 0.0 && float.abs() < INTEGERS => {
+            Cow::Owned(Value::Integer(float.0 as i128))
+        }
+        key => Cow::Borrowed(key),
+    }
+}
+
+/// Maps with the same entries are equal regardless of insertion order, so they must hash the same
+/// regardless of it too
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let entries = self.0.iter().fold(0u64, |acc, entry| {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            acc.wrapping_add(hasher.finish())
+        });
+
+        self.0.len().hash(state);
+        entries.hash(state);
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (idx, (key, value)) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", key.0.nested(), value.nested())?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -0,0 +1,177 @@
+//! Numbers that carry a unit of measurement, with the `units` feature
+//!
+//! A quantity is held in SI base units whatever it was written in, so `1km` and `1000m` are the
+//! same value. Its dimension records the power of each base unit, which `*` and `/` combine and
+//! `+`, `-` and comparisons require to match.
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Neg};
+
+use super::value::Value;
+
+/// The SI base units, in the order that their powers are held in a [`Dimension`]
+const BASE: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// The units that quantities can be written in: name, size in base units, dimension
+const UNITS: &[(&str, f64, [i8; 7])] = &[
+    ("m", 1.0, [1, 0, 0, 0, 0, 0, 0]),
+    ("km", 1e3, [1, 0, 0, 0, 0, 0, 0]),
+    ("cm", 1e-2, [1, 0, 0, 0, 0, 0, 0]),
+    ("mm", 1e-3, [1, 0, 0, 0, 0, 0, 0]),
+    ("kg", 1.0, [0, 1, 0, 0, 0, 0, 0]),
+    ("g", 1e-3, [0, 1, 0, 0, 0, 0, 0]),
+    ("s", 1.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("ms", 1e-3, [0, 0, 1, 0, 0, 0, 0]),
+    ("min", 60.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("h", 3600.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("A", 1.0, [0, 0, 0, 1, 0, 0, 0]),
+    ("K", 1.0, [0, 0, 0, 0, 1, 0, 0]),
+    ("mol", 1.0, [0, 0, 0, 0, 0, 1, 0]),
+    ("cd", 1.0, [0, 0, 0, 0, 0, 0, 1]),
+    ("Hz", 1.0, [0, 0, -1, 0, 0, 0, 0]),
+    ("N", 1.0, [1, 1, -2, 0, 0, 0, 0]),
+    ("Pa", 1.0, [-1, 1, -2, 0, 0, 0, 0]),
+    ("J", 1.0, [2, 1, -2, 0, 0, 0, 0]),
+    ("W", 1.0, [2, 1, -3, 0, 0, 0, 0]),
+];
+
+/// The powers of the SI base units that a quantity is measured in
+#[derive(Hash, Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Dimension([i8; 7]);
+
+impl Dimension {
+    /// Whether the dimension has no units at all, as plain numbers do
+    pub fn is_none(self) -> bool {
+        self.0 == [0; 7]
+    }
+
+    fn combine(self, other: Self, sign: i8) -> Self {
+        let mut powers = self.0;
+        for (power, other) in powers.iter_mut().zip(other.0) {
+            *power += sign * other;
+        }
+        Self(powers)
+    }
+}
+
+/// Written as the base units, such as `m*kg/s^2`
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = |positive: bool| {
+            BASE.iter()
+                .zip(self.0)
+                .filter(|(_, power)| *power != 0 && (*power > 0) == positive)
+                .map(|(unit, power)| match power.abs() {
+                    1 => (*unit).to_string(),
+                    power => format!("{unit}^{power}"),
+                })
+                .collect::<Vec<_>>()
+                .join("*")
+        };
+
+        match (units(true), units(false)) {
+            (above, below) if above.is_empty() && below.is_empty() => write!(f, "no units"),
+            (above, below) if below.is_empty() => write!(f, "{above}"),
+            (above, below) if above.is_empty() => write!(f, "1/{below}"),
+            (above, below) => write!(f, "{above}/{below}"),
+        }
+    }
+}
+
+/// A number along with the unit it measures
+#[derive(Hash, Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Quantity {
+    /// the size of the quantity in SI base units
+    pub magnitude: OrderedFloat<f64>,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    /// A number without a unit
+    pub const fn scalar(magnitude: f64) -> Self {
+        Self {
+            magnitude: OrderedFloat(magnitude),
+            dimension: Dimension([0; 7]),
+        }
+    }
+
+    /// One of the unit called `name`, if there is one
+    pub fn unit(name: &str) -> Option<Self> {
+        UNITS
+            .iter()
+            .find(|(unit, ..)| *unit == name)
+            .map(|&(_, size, powers)| Self {
+                magnitude: OrderedFloat(size),
+                dimension: Dimension(powers),
+            })
+    }
+
+    /// One of the compound unit `units`, which is written like `kg*m/s^2`
+    pub fn parse(units: &str) -> Option<Self> {
+        let mut parts = units.split('/');
+        let mut quantity = Self::factors(parts.next()?)?;
+        for part in parts {
+            quantity = quantity.per(Self::factors(part)?);
+        }
+        Some(quantity)
+    }
+
+    /// The product of units separated by `*`, each optionally raised to a power with `^`
+    fn factors(units: &str) -> Option<Self> {
+        units
+            .split('*')
+            .try_fold(Self::scalar(1.0), |product, factor| {
+                let (name, power) = match factor.trim().split_once('^') {
+                    Some((name, power)) => (name, power.parse::<i8>().ok()?),
+                    None => (factor.trim(), 1),
+                };
+                let unit = Self::unit(name)?;
+                Some(product.times(Self {
+                    magnitude: OrderedFloat(unit.magnitude.powi(power.into())),
+                    dimension: Dimension([0; 7]).combine(unit.dimension, power),
+                }))
+            })
+    }
+
+    #[must_use]
+    pub fn times(self, other: Self) -> Self {
+        Self {
+            magnitude: self.magnitude * other.magnitude,
+            dimension: self.dimension.combine(other.dimension, 1),
+        }
+    }
+
+    #[must_use]
+    pub fn per(self, other: Self) -> Self {
+        Self {
+            magnitude: self.magnitude / other.magnitude,
+            dimension: self.dimension.combine(other.dimension, -1),
+        }
+    }
+
+    /// The quantity as a value, which is a plain float if its units have cancelled out
+    pub fn into_value(self) -> Value {
+        if self.dimension.is_none() {
+            Value::Float(self.magnitude)
+        } else {
+            Value::Quantity(self)
+        }
+    }
+}
+
+impl Neg for Quantity {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            magnitude: -self.magnitude,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.magnitude, self.dimension)
+    }
+}
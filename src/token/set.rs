@@ -0,0 +1,103 @@
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use super::{map::Key, value::Value};
+
+/// A collection of distinct values, which remembers the order that they were inserted in
+///
+/// Elements are told apart as the keys of a [`Map`](super::Map) are, so `1` and `1.0` are the
+/// same element, as is every NaN. Each element keeps the value it was first inserted as.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Set(IndexSet<Key>);
+
+/// The elements of a set, in the order that they were inserted
+pub type IntoValues = std::iter::Map<indexmap::set::IntoIter<Key>, fn(Key) -> Value>;
+
+impl Set {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, value: &Value) -> bool {
+        self.0.contains(&Key(value.clone()))
+    }
+
+    /// Inserts `value`, returning whether it was not already present
+    pub fn insert(&mut self, value: Value) -> bool {
+        self.0.insert(Key(value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        self.0.iter().map(|element| &element.0)
+    }
+
+    pub fn into_values(self) -> IntoValues {
+        self.0.into_iter().map(|element| element.0)
+    }
+
+    /// The elements of either set, those of `self` first
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self(self.0.union(&other.0).cloned().collect())
+    }
+
+    /// The elements of `self` that are also in `other`
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self(self.0.intersection(&other.0).cloned().collect())
+    }
+
+    /// The elements of `self` that are not in `other`
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        Self(self.0.difference(&other.0).cloned().collect())
+    }
+}
+
+impl FromIterator<Value> for Set {
+    fn from_iter<T: IntoIterator<Item = Value>>(iter: T) -> Self {
+        Self(iter.into_iter().map(Key).collect())
+    }
+}
+
+/// Sets with the same elements are equal regardless of insertion order, so they must hash the
+/// same regardless of it too
+impl Hash for Set {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let elements = self.0.iter().fold(0u64, |acc, element| {
+            let mut hasher = DefaultHasher::new();
+            element.hash(&mut hasher);
+            acc.wrapping_add(hasher.finish())
+        });
+
+        self.0.len().hash(state);
+        elements.hash(state);
+    }
+}
+
+/// Written as the literal that creates it, such as `set{1, "a"}`
+impl fmt::Display for Set {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "set{{")?;
+        for (idx, element) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", element.0.nested())?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -5,8 +5,22 @@ pub enum Type {
     String,
     Integer,
     Float,
+    #[cfg(feature = "decimal")]
+    Decimal,
+    #[cfg(feature = "units")]
+    Quantity,
     Boolean,
     Null,
+    Function,
+    Class,
+    Trait,
+    Instance,
+    Ast,
+    List,
+    Range,
+    Tuple,
+    Map,
+    Set,
 }
 
 impl From<Value> for Type {
@@ -15,8 +29,22 @@ impl From<Value> for Type {
             Value::String(_) => Self::String,
             Value::Integer(_) => Self::Integer,
             Value::Float(_) => Self::Float,
+            #[cfg(feature = "decimal")]
+            Value::Decimal(_) => Self::Decimal,
+            #[cfg(feature = "units")]
+            Value::Quantity(_) => Self::Quantity,
             Value::Boolean(_) => Self::Boolean,
             Value::Null => Self::Null,
+            Value::NativeFn(_) | Value::Function(_) => Self::Function,
+            Value::Class(_) => Self::Class,
+            Value::Trait(_) => Self::Trait,
+            Value::Instance(_) => Self::Instance,
+            Value::Ast(_) => Self::Ast,
+            Value::List(_) => Self::List,
+            Value::Range(..) => Self::Range,
+            Value::Tuple(_) => Self::Tuple,
+            Value::Map(_) => Self::Map,
+            Value::Set(_) => Self::Set,
         }
     }
 }
@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
-use std::{fmt, fmt::Display};
+use std::{fmt, fmt::Display, rc::Rc};
+
+use super::{map::Map, set::Set};
+use crate::{
+    ast::Quoted,
+    interpreter::{Class, Function, Instance, NativeFn, Trait},
+};
 
 macro_rules! impl_from {
     ($wrapper:path; $inner_type:ty; $($from:ty),+) => {
@@ -15,9 +21,39 @@ macro_rules! impl_from {
 pub enum Value {
     String(String),
     Integer(i128),
+    /// Equality and hashing treat every NaN as equal, and `0.0` as equal to `-0.0`, which makes
+    /// floats usable as [`Map`] keys
     Float(ordered_float::OrderedFloat<f64>),
+    /// An exact decimal number, written with a `d` suffix as in `1.23d`. It keeps the digits it was
+    /// written with, so `1.50d` displays as `1.50`
+    #[cfg(feature = "decimal")]
+    Decimal(rust_decimal::Decimal),
+    /// A number measured in a unit, written with the unit as a suffix as in `5m`
+    #[cfg(feature = "units")]
+    Quantity(super::quantity::Quantity),
     Boolean(bool),
     Null,
+    /// Functions only exist at runtime, so can never be (de)serialised
+    #[serde(skip)]
+    NativeFn(Rc<NativeFn>),
+    #[serde(skip)]
+    Function(Function),
+    #[serde(skip)]
+    Class(Rc<Class>),
+    #[serde(skip)]
+    Trait(Rc<Trait>),
+    #[serde(skip)]
+    Instance(Rc<Instance>),
+    /// A quoted piece of syntax tree
+    Ast(Box<Quoted>),
+    List(Vec<Self>),
+    /// The integers from the first bound up to but not including the second, as created by
+    /// `range`. They are only produced as it is iterated over, so a range of any length is cheap.
+    Range(i128, i128),
+    /// A fixed number of values, such as those returned together by a function
+    Tuple(Vec<Self>),
+    Map(Map),
+    Set(Set),
 }
 
 impl_from!(Value::Integer; i128; u8, u16, u32, u64, i8, i16, i32, i64, i128);
@@ -30,9 +66,57 @@ impl Display for Value {
         match self {
             Self::String(a) => write!(f, "{a}"),
             Self::Integer(a) => write!(f, "{a}"),
-            Self::Float(a) => write!(f, "{a}"),
+            // the shortest representation that parses back to the same float, always with a
+            // fractional part or exponent so that it cannot be mistaken for an integer
+            Self::Float(a) => write!(f, "{}", ryu::Buffer::new().format(a.0)),
+            #[cfg(feature = "decimal")]
+            Self::Decimal(a) => write!(f, "{a}"),
+            #[cfg(feature = "units")]
+            Self::Quantity(a) => write!(f, "{a}"),
             Self::Boolean(a) => write!(f, "{a}"),
             Self::Null => write!(f, "Null"),
+            Self::NativeFn(native) => write!(f, "{native}"),
+            Self::Function(fun) => write!(f, "{fun}"),
+            Self::Class(class) => write!(f, "{class}"),
+            Self::Trait(r#trait) => write!(f, "{trait}"),
+            Self::Instance(instance) => write!(f, "{instance}"),
+            Self::Ast(quoted) => write!(f, "<ast {quoted}>"),
+            Self::List(list) => {
+                write!(f, "[")?;
+                for (idx, elem) in list.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", elem.nested())?;
+                }
+                write!(f, "]")
+            }
+            Self::Tuple(tuple) => {
+                write!(f, "(")?;
+                for (idx, elem) in tuple.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", elem.nested())?;
+                }
+                write!(f, ")")
+            }
+            Self::Range(start, end) => write!(f, "range({start}, {end})"),
+            Self::Map(map) => write!(f, "{map}"),
+            Self::Set(set) => write!(f, "{set}"),
+        }
+    }
+}
+
+impl Value {
+    /// Displays the value as an element of a collection, where strings are quoted and decimals
+    /// suffixed to tell them apart from other values
+    pub(crate) fn nested(&self) -> String {
+        match self {
+            Self::String(string) => format!("\"{string}\""),
+            #[cfg(feature = "decimal")]
+            Self::Decimal(decimal) => format!("{decimal}d"),
+            value => value.to_string(),
         }
     }
 }
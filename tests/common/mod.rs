@@ -0,0 +1,22 @@
+//! Helpers shared by the integration tests
+use atium::{
+    atium::{Atium, Capabilities, Options},
+    error::describe,
+};
+
+/// The value of `src` as `print` displays it, or the kind of the first error. Files can only be
+/// touched if `fs` is true.
+pub fn evaluate(src: &str, fs: bool) -> String {
+    Atium::new(src, None)
+        .with_options(Options {
+            capabilities: Capabilities { fs },
+            ..Options::default()
+        })
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::evaluate)
+        .map_or_else(
+            |errs| describe(&errs[0]).kind.to_string(),
+            |value| value.to_string(),
+        )
+}
@@ -0,0 +1,122 @@
+//! Runs the scripts in `tests/conformance`, which follow the format of the craftinginterpreters
+//! test suite so that its tests can be dropped in unchanged.
+//!
+//! Each line of a script's stdout must match, in order, the `// expect: <output>` comments in
+//! the script. A `// expect runtime error: <message>` comment requires that `<message>` is
+//! reported. A script that atium deliberately diverges on is marked with
+//! `// diverges: <reason>`; it is skipped, but is reported if it unexpectedly passes so that the
+//! marker can be removed. A script marked with `// requires: <feature>` only runs when atium is
+//! built with that feature.
+//!
+//! Run with `cargo test --features conformance`.
+#![cfg(feature = "conformance")]
+
+use std::{fs, path::Path, process::Command};
+
+struct Expectations {
+    output: Vec<String>,
+    runtime_error: Option<String>,
+    divergence: Option<String>,
+    feature: Option<String>,
+}
+
+impl Expectations {
+    fn parse(src: &str) -> Self {
+        let comment = |line: &str, marker: &str| {
+            line.split_once(marker)
+                .map(|(_, rest)| rest.trim().to_string())
+        };
+
+        let mut expectations = Self {
+            output: vec![],
+            runtime_error: None,
+            divergence: None,
+            feature: None,
+        };
+        for line in src.lines() {
+            if let Some(output) = comment(line, "// expect: ") {
+                expectations.output.push(output);
+            } else if let Some(error) = comment(line, "// expect runtime error: ") {
+                expectations.runtime_error = Some(error);
+            } else if let Some(reason) = comment(line, "// diverges: ") {
+                expectations.divergence = Some(reason);
+            } else if let Some(feature) = comment(line, "// requires: ") {
+                expectations.feature = Some(feature);
+            }
+        }
+        expectations
+    }
+}
+
+/// Whether atium is built with `feature`
+fn enabled(feature: &str) -> bool {
+    match feature {
+        "decimal" => cfg!(feature = "decimal"),
+        "hash" => cfg!(feature = "hash"),
+        "regex" => cfg!(feature = "regex"),
+        "toml" => cfg!(feature = "toml"),
+        "units" => cfg!(feature = "units"),
+        "yaml" => cfg!(feature = "yaml"),
+        _ => panic!("unknown feature {feature}"),
+    }
+}
+
+/// Runs `script`, describing how its behaviour differed from its expectations
+fn check(script: &Path, expectations: &Expectations) -> Result<(), String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg(script)
+        .output()
+        .map_err(|err| format!("failed to run: {err}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let actual = stdout.lines().collect::<Vec<_>>();
+    if actual != expectations.output {
+        return Err(format!(
+            "expected output {:?} but got {actual:?}",
+            expectations.output
+        ));
+    }
+
+    match &expectations.runtime_error {
+        Some(error) if !stderr.contains(error.as_str()) => Err(format!(
+            "expected runtime error \"{error}\" but got \"{stderr}\""
+        )),
+        None if !stderr.is_empty() => Err(format!("unexpected errors: \"{stderr}\"")),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn conformance() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let mut scripts = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<_>>();
+    scripts.sort();
+
+    let mut failures = vec![];
+    for script in scripts {
+        let src = fs::read_to_string(&script).unwrap();
+        let expectations = Expectations::parse(&src);
+        let name = script.file_name().unwrap().to_string_lossy().to_string();
+        if let Some(feature) = &expectations.feature {
+            if !enabled(feature) {
+                continue;
+            }
+        }
+
+        match (check(&script, &expectations), &expectations.divergence) {
+            (Ok(()), Some(_)) => {
+                failures.push(format!("{name}: passes, remove its `// diverges:` marker"));
+            }
+            (Err(err), None) => failures.push(format!("{name}: {err}")),
+            (Err(_), Some(reason)) => println!("{name}: skipped, diverges: {reason}"),
+            (Ok(()), None) => (),
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,58 @@
+//! Checks that delimited files round trip through `writeCsv` and `readCsv`, and that programs can
+//! only touch files when the host grants them the fs capability.
+use std::{env, fs, process};
+
+use common::evaluate;
+
+mod common;
+
+#[test]
+fn rows_round_trip() {
+    for (extension, separator) in [("csv", ','), ("tsv", '\t')] {
+        let file = |name: &str| {
+            let name = format!("atium-csv-{}-{name}.{extension}", process::id());
+            let path = env::temp_dir().join(name);
+            path.display().to_string().replace('\\', "/")
+        };
+        let (input, output) = (file("in"), file("out"));
+
+        let quoted = format!("\"Smith{separator} J\"");
+        let csv =
+            format!("name{separator}quote\n{quoted}{separator}\"say \"\"hi\"\"\"\n2{separator}\n");
+        fs::write(&input, &csv).unwrap();
+
+        let read = format!("readCsv(\"{input}\")");
+        assert_eq!(
+            evaluate(&read, true),
+            format!(
+                "[{{\"name\": \"Smith{separator} J\", \"quote\": \"say \"hi\"\"}}, \
+                 {{\"name\": \"2\", \"quote\": \"\"}}]"
+            )
+        );
+
+        let write = format!("writeCsv(\"{output}\", {read});");
+        assert_eq!(evaluate(&write, true), "Null");
+        assert_eq!(fs::read_to_string(&output).unwrap(), csv);
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+}
+
+#[test]
+fn files_need_the_fs_capability() {
+    assert_eq!(evaluate("readCsv(\"data.csv\")", false), "CapabilityDenied");
+    assert_eq!(
+        evaluate("writeCsv(\"data.csv\", 1)", false),
+        "CapabilityDenied"
+    );
+}
+
+#[test]
+fn missing_files_are_errors() {
+    assert_eq!(evaluate("readCsv(\"/nonexistent/data.csv\")", true), "Io");
+    assert_eq!(
+        evaluate("readCsv(\"data.csv\", \";;\")", true),
+        "InvalidArgument"
+    );
+}
@@ -0,0 +1,11 @@
+//! Checks that decimal literals are an error without the `decimal` feature. With it, decimals are
+//! covered by `tests/conformance/decimal.lox`.
+#![cfg(not(feature = "decimal"))]
+use common::evaluate;
+
+mod common;
+
+#[test]
+fn decimal_literals_need_the_feature() {
+    assert_eq!(evaluate("1.5d", false), "DecimalsDisabled");
+}
@@ -0,0 +1,64 @@
+//! Checks that examples are found in documentation, and that `atium test` reports whether they
+//! hold.
+use std::{
+    env, fs,
+    process::{self, Command},
+};
+
+use atium::doctest;
+
+const SOURCE: &str = "
+/// Adds two numbers
+///
+/// >>> add(1, 2)
+/// 3
+/// >>> add(1, 2);
+///
+/// Strings are concatenated
+/// >>> add(\"a\", \"b\")
+/// \"ab\"
+fun add(a, b) { return a + b; }
+
+fun undocumented() {}
+";
+
+#[test]
+fn examples_are_extracted() {
+    let examples = doctest::extract(SOURCE, None)
+        .unwrap()
+        .into_iter()
+        .map(|example| (example.function, example.source, example.expected))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        examples,
+        [
+            ("add", "add(1, 2)", Some("3")),
+            ("add", "add(1, 2);", None),
+            ("add", "add(\"a\", \"b\")", Some("\"ab\"")),
+        ]
+        .map(|(function, source, expected)| (
+            function.to_string(),
+            source.to_string(),
+            expected.map(str::to_string)
+        ))
+    );
+}
+
+#[test]
+fn failing_examples_fail_the_run() {
+    for (expected, passes) in [("3", true), ("4", false)] {
+        let script = env::temp_dir().join(format!("atium-doctest-{}-{expected}.at", process::id()));
+        let source =
+            format!("/// >>> add(1, 2)\n/// {expected}\nfun add(a, b) {{ return a + b; }}");
+        fs::write(&script, source).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+            .arg("test")
+            .arg(&script)
+            .output()
+            .unwrap();
+        fs::remove_file(&script).unwrap();
+
+        assert_eq!(output.status.success(), passes, "expecting {expected}");
+    }
+}
@@ -0,0 +1,68 @@
+//! Runs the same inputs in the REPL, which echoes the value of each expression statement, and as
+//! a script, which does not.
+use std::{
+    env, fs,
+    io::Write,
+    process::{self, Command, Output, Stdio},
+};
+
+/// Each input, along with what it outputs in the REPL and in a script
+const INPUTS: [(&str, Option<&str>, Option<&str>); 8] = [
+    ("1 + 2;", Some("3"), None),
+    ("\"s\";", Some("\"s\""), None),
+    ("var x = 1;", None, None),
+    ("x = 5;", Some("5"), None),
+    ("fun f() {}", None, None),
+    // calls that return nothing have nothing to echo
+    ("f();", None, None),
+    ("print x;", Some("5"), Some("5")),
+    ("x", Some("5"), None),
+];
+
+fn stdout(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn repl_echoes_expressions() {
+    let mut repl = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["--no-init", "--prompt", ""])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let inputs = INPUTS.map(|(input, ..)| input).join("\n");
+    repl.stdin
+        .take()
+        .unwrap()
+        .write_all(inputs.as_bytes())
+        .unwrap();
+
+    let output = repl.wait_with_output().unwrap();
+    let expected = INPUTS
+        .iter()
+        .filter_map(|(_, repl, _)| *repl)
+        .collect::<Vec<_>>();
+    assert_eq!(stdout(&output), expected);
+}
+
+#[test]
+fn scripts_do_not_echo() {
+    let script = env::temp_dir().join(format!("atium-echo-{}.lox", process::id()));
+    fs::write(&script, INPUTS.map(|(input, ..)| input).join("\n")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg(&script)
+        .output()
+        .unwrap();
+    fs::remove_file(&script).unwrap();
+
+    let expected = INPUTS
+        .iter()
+        .filter_map(|(.., script)| *script)
+        .collect::<Vec<_>>();
+    assert_eq!(stdout(&output), expected);
+}
@@ -0,0 +1,229 @@
+//! Checks that hosts can give programs helpers of their own, written in atium as a prelude or in
+//! Rust as natives
+use std::{cell::Cell, path::Path, rc::Rc};
+
+use atium::{
+    atium::{global_env, Atium, Options, ParseLimits},
+    error::describe,
+    interpreter::NativeFn,
+    token::{Map, Value},
+};
+
+fn options(prelude: &str) -> Options {
+    Options {
+        prelude: Some(prelude.to_string()),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn programs_see_the_prelude() {
+    let result = Atium::new("helper(1);", None)
+        .with_options(options("fun helper(x) { return x; }"))
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::interpret);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn shared_scopes_see_the_prelude() {
+    let options = options("var greeting = \"hello\";");
+    let env = global_env(&options).unwrap();
+    for src in ["var copy = greeting;", "copy = greeting;"] {
+        let result = Atium::with_env(src, None, env.clone())
+            .with_options(options.clone())
+            .lex()
+            .and_then(Atium::parse)
+            .and_then(Atium::interpret);
+        assert!(result.is_ok(), "{src}");
+    }
+}
+
+#[test]
+fn prelude_errors_are_attributed_to_it() {
+    let errs = Atium::new("", None)
+        .with_options(options("print 1 / 0;"))
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::interpret)
+        .unwrap_err();
+
+    let span = describe(&errs[0]).span.unwrap();
+    assert_eq!(span.file.unwrap().name(), Path::new("<prelude>"));
+}
+
+#[test]
+fn natives_are_callable_from_the_prelude_and_programs() {
+    let calls = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&calls);
+    let record = NativeFn::new("record", 1..=1, move |_, _, args| {
+        counter.set(counter.get() + 1);
+        Ok(args.into_iter().next().unwrap_or(Value::Null))
+    });
+
+    let options = Options {
+        natives: vec![record],
+        ..options("record(1);")
+    };
+    let result = Atium::new("record(clock());", None)
+        .with_options(options)
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::interpret);
+
+    assert!(result.is_ok());
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn isolated_eval_sees_the_prelude_and_natives() {
+    let isolated = NativeFn::new("isolated", 0..=0, |_, _, _| {
+        Ok(Value::Map(Map::from_iter([(
+            Value::String("isolated".to_string()),
+            Value::Boolean(true),
+        )])))
+    });
+    let options = Options {
+        natives: vec![isolated],
+        ..options("fun helper(x) { return x; }")
+    };
+    let cases = [
+        ("eval(\"helper(isolated())\", isolated());", true),
+        ("var local = 1; eval(\"local\", isolated());", false),
+    ];
+
+    for (src, ok) in cases {
+        let result = Atium::new(src, None)
+            .with_options(options.clone())
+            .lex()
+            .and_then(Atium::parse)
+            .and_then(Atium::interpret);
+        assert_eq!(result.is_ok(), ok, "{src}");
+    }
+}
+
+/// Parses `src` within `limits`, returning the kind and message of the first error
+fn parse_within(src: &str, limits: ParseLimits) -> Option<(String, String)> {
+    let options = Options {
+        parse_limits: Some(limits),
+        ..Options::default()
+    };
+    let errs = Atium::new(src, None)
+        .with_options(options)
+        .lex()
+        .and_then(Atium::parse)
+        .err()?;
+
+    let described = describe(&errs[0]);
+    Some((described.kind.to_string(), described.message))
+}
+
+#[test]
+fn programs_within_limits_parse() {
+    let limits = ParseLimits {
+        tokens: 7,
+        nodes: 4,
+        depth: 4,
+    };
+    assert_eq!(parse_within("print (1 + 2);", limits), None);
+}
+
+#[test]
+fn programs_exceeding_limits_do_not_parse() {
+    let limits = ParseLimits {
+        tokens: 100_000,
+        nodes: 100_000,
+        depth: 100_000,
+    };
+    let deep = format!("{}1{};", "(".repeat(10_000), ")".repeat(10_000));
+    let nested = format!("{}{}", "fun f() {".repeat(10_000), "}".repeat(10_000));
+    let cases = [
+        (
+            "print 1;".repeat(50),
+            ParseLimits {
+                tokens: 100,
+                ..limits
+            },
+            "more than 100 tokens",
+        ),
+        (
+            "1;".repeat(101),
+            ParseLimits {
+                nodes: 100,
+                ..limits
+            },
+            "more than 100 syntax tree nodes",
+        ),
+        (
+            deep,
+            ParseLimits {
+                depth: 100,
+                ..limits
+            },
+            "more than 100 levels of nesting",
+        ),
+        (
+            nested,
+            ParseLimits {
+                depth: 100,
+                ..limits
+            },
+            "more than 100 levels of nesting",
+        ),
+    ];
+
+    for (src, limits, expected) in cases {
+        let (kind, message) = parse_within(&src, limits).expect("the program is too large");
+        assert_eq!(kind, "ProgramTooLarge");
+        assert!(message.contains(expected), "{message}");
+    }
+}
+
+#[test]
+fn malformed_programs_are_errors() {
+    let limits = ParseLimits {
+        tokens: 100,
+        nodes: 100,
+        depth: 100,
+    };
+    let cases = [
+        ("print ;", "ExpectedExpression"),
+        ("var x = );", "ExpectedExpression"),
+        (
+            "print 1000000000000000000000000000000000000000000;",
+            "IntegerTooLarge",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let kind = parse_within(src, limits).map(|(kind, _)| kind);
+        assert_eq!(kind.as_deref(), Some(expected), "{src}");
+    }
+}
+
+#[test]
+fn recursion_is_limited_to_max_depth() {
+    let options = Options {
+        max_depth: Some(20),
+        ..Options::default()
+    };
+    let recurse = "fun r(n) { match n { 0 => { return 0; } _ => { return 1 + r(n - 1); } } }";
+    let tail = "fun t(n) { match n { 0 => { return 0; } _ => { return t(n - 1); } } }";
+    let cases = [
+        (format!("{recurse} r(19);"), None),
+        (format!("{recurse} r(20);"), Some("StackOverflow")),
+        // calls in tail position return before the call they make is made
+        (format!("{tail} t(1000);"), None),
+    ];
+
+    for (src, expected) in cases {
+        let result = Atium::new(&src, None)
+            .with_options(options.clone())
+            .lex()
+            .and_then(Atium::parse)
+            .and_then(Atium::interpret);
+        let kind = result.err().map(|errs| describe(&errs[0]).kind);
+        assert_eq!(kind, expected, "{src}");
+    }
+}
@@ -0,0 +1,18 @@
+//! Checks that the grammar derived from the binding powers of operators gives each level of
+//! precedence its associativity.
+use atium::grammar::{grammar, render, Notation};
+
+#[test]
+fn operators_associate_as_they_are_parsed() {
+    let ebnf = render(&grammar(), Notation::Ebnf);
+    for rule in [
+        "assignment = logicOr , [ \"=\" , assignment ] ;",
+        "equality = comparison , { ( \"==\" | \"!=\" ) , comparison } ;",
+        "comparison = term , { ( \">\" | \">=\" | \"<\" | \"<=\" | \"is\" ) , term } ;",
+    ] {
+        assert!(
+            ebnf.lines().any(|line| line == rule),
+            "{rule} not in\n{ebnf}"
+        );
+    }
+}
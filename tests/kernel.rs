@@ -0,0 +1,46 @@
+//! Checks that the kernel evaluates cells in one global scope, replying with what each printed,
+//! its value and its errors.
+use atium::{atium::Options, kernel};
+use serde_json::{json, Value};
+
+fn replies(cells: &[Value]) -> Vec<Value> {
+    let input = cells
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut output = vec![];
+    kernel::serve(input.as_bytes(), &mut output, &Options::default()).unwrap();
+
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn cells_share_a_scope() {
+    let replies = replies(&[
+        json!({"id": 1, "code": "var x = 2; print x; x * 3"}),
+        json!({"id": "second", "code": "\"x is \" + str(x)"}),
+        json!({"code": "x / 0;"}),
+        json!({"id": 4}),
+    ]);
+
+    assert_eq!(replies[0]["id"], json!(1));
+    assert_eq!(replies[0]["output"], json!("2\n"));
+    assert_eq!(replies[0]["value"], json!("6"));
+    assert_eq!(replies[0]["diagnostics"], json!([]));
+
+    assert_eq!(replies[1]["id"], json!("second"));
+    assert_eq!(replies[1]["value"], json!("\"x is 2\""));
+
+    let diagnostic = &replies[2]["diagnostics"][0];
+    assert_eq!(replies[2]["value"], Value::Null);
+    assert_eq!(diagnostic["kind"], json!("DivisionByZero"));
+    assert_eq!(diagnostic["file"], json!("cell:3"));
+    assert_eq!(diagnostic["line"], json!(1));
+
+    assert_eq!(replies[3]["diagnostics"][0]["kind"], json!("InvalidCell"));
+}
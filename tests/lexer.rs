@@ -0,0 +1,170 @@
+//! Checks where the lexer draws the boundaries between identifiers, numbers and keywords, and
+//! that printed floats lex back to the same number
+use atium::{
+    atium::Atium,
+    lexer::Cursor,
+    token::{TokenKind, Value},
+};
+use ordered_float::OrderedFloat;
+
+/// Lexes `src`, returning the kind and lexeme of each token
+fn lex(src: &str) -> Vec<(TokenKind, String)> {
+    Cursor::new::<&str>(src, None)
+        .map(|token| {
+            let token = token.unwrap_or_else(|err| panic!("{src} failed to lex: {err}"));
+            (token.kind.clone(), token.lex())
+        })
+        .collect()
+}
+
+/// Lexes `src`, ignoring any characters that cannot start a token
+fn lex_valid(src: &str) -> Vec<(TokenKind, String)> {
+    Cursor::new::<&str>(src, None)
+        .filter_map(Result::ok)
+        .map(|token| (token.kind.clone(), token.lex()))
+        .collect()
+}
+
+fn ident(lex: &str) -> (TokenKind, String) {
+    (TokenKind::Identifier, lex.to_string())
+}
+
+fn number(lex: &str) -> (TokenKind, String) {
+    (TokenKind::Number, lex.to_string())
+}
+
+#[test]
+fn identifiers() {
+    for src in [
+        "a",
+        "Z",
+        "_",
+        "__",
+        "_a",
+        "a_b",
+        "foo0",
+        "foo10",
+        "x1_2",
+        "a0_",
+        "CamelCase",
+    ] {
+        assert_eq!(lex(src), [ident(src)], "{src}");
+    }
+}
+
+#[test]
+fn every_ascii_character_after_the_start() {
+    for c in (0..=127u8).map(char::from) {
+        let src = format!("a{c}");
+        let tokens = lex_valid(&src);
+        if c.is_ascii_alphanumeric() || c == '_' {
+            assert_eq!(tokens, [ident(&src)], "{src:?}");
+        } else if tokens.first() != Some(&ident("a")) {
+            panic!("{src:?} lexed as {tokens:?}");
+        }
+    }
+}
+
+#[test]
+fn every_ascii_character_at_the_start() {
+    // a quote would start a string containing the identifier
+    for c in (0..=127u8).map(char::from).filter(|&c| c != '"') {
+        let src = format!("{c}a");
+        let tokens = lex_valid(&src);
+        if c.is_ascii_alphabetic() || c == '_' {
+            assert_eq!(tokens, [ident(&src)], "{src:?}");
+        } else if c.is_ascii_digit() {
+            assert_eq!(tokens, [number(&c.to_string()), ident("a")], "{src:?}");
+        } else if tokens.last() != Some(&ident("a")) {
+            panic!("{src:?} lexed as {tokens:?}");
+        }
+    }
+}
+
+#[test]
+fn numbers_end_where_identifiers_start() {
+    assert_eq!(lex("0a"), [number("0"), ident("a")]);
+    assert_eq!(lex("10_"), [number("10"), ident("_")]);
+    assert_eq!(lex("1.5e"), [number("1.5"), ident("e")]);
+    assert_eq!(lex("a 0"), [ident("a"), number("0")]);
+}
+
+#[test]
+fn dots_after_numbers() {
+    let dot = || (TokenKind::Dot, String::from("."));
+    assert_eq!(lex("1.5"), [number("1.5")]);
+    assert_eq!(lex("1.abs"), [number("1"), dot(), ident("abs")]);
+    assert_eq!(lex("1."), [number("1"), dot()]);
+    assert_eq!(lex("1.2.3"), [number("1.2"), dot(), number("3")]);
+    assert_eq!(lex("1.5.abs"), [number("1.5"), dot(), ident("abs")]);
+}
+
+#[test]
+fn exponents() {
+    let plus = || (TokenKind::Plus, String::from("+"));
+    for src in ["1e20", "2.5E-3", "1e+3", "0e0"] {
+        assert_eq!(lex(src), [number(src)], "{src}");
+    }
+    assert_eq!(lex("1e"), [number("1"), ident("e")]);
+    assert_eq!(lex("1ex"), [number("1"), ident("ex")]);
+    assert_eq!(lex("1e+"), [number("1"), ident("e"), plus()]);
+    assert_eq!(lex("1e2e3"), [number("1e2"), ident("e3")]);
+}
+
+/// Every float prints in a form that lexes and evaluates back to the same float
+#[test]
+fn floats_round_trip_through_print() {
+    for float in [
+        0.1 + 0.2,
+        1.0,
+        -0.0,
+        1e15,
+        1e16,
+        1e20,
+        1.5e-7,
+        -2.5e-300,
+        123_456_789.125,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        5e-324,
+    ] {
+        let printed = Value::Float(OrderedFloat(float)).to_string();
+        let value = Atium::new(&printed, None)
+            .lex()
+            .and_then(Atium::parse)
+            .and_then(Atium::evaluate)
+            .unwrap_or_else(|errs| panic!("{printed} failed: {errs:?}"));
+        let Value::Float(parsed) = value else {
+            panic!("{printed} evaluated to {value}");
+        };
+        assert_eq!(parsed.0.to_bits(), float.to_bits(), "{printed}");
+    }
+}
+
+#[test]
+fn ellipses() {
+    let dot = || (TokenKind::Dot, String::from("."));
+    let ellipsis = || (TokenKind::Ellipsis, String::from("..."));
+    assert_eq!(lex("...rest"), [ellipsis(), ident("rest")]);
+    assert_eq!(lex(".."), [dot(), dot()]);
+    assert_eq!(lex("...."), [ellipsis(), dot()]);
+}
+
+#[test]
+fn keywords() {
+    for (keyword, kind) in TokenKind::KEYWORDS {
+        assert_eq!(lex(keyword), [(kind.clone(), keyword.to_string())]);
+        for suffix in ["0", "9", "_", "s"] {
+            let src = format!("{keyword}{suffix}");
+            assert_eq!(lex(&src), [ident(&src)], "{src}");
+        }
+        let src = format!("_{keyword}");
+        assert_eq!(lex(&src), [ident(&src)], "{src}");
+    }
+}
+
+#[test]
+fn keywords_are_case_sensitive() {
+    assert_eq!(lex("While"), [ident("While")]);
+    assert_eq!(lex("TRUE"), [ident("TRUE")]);
+}
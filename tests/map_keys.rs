@@ -0,0 +1,54 @@
+//! Checks that map keys and set elements are the same exactly when `==` says they are, for
+//! floats in particular.
+use atium::token::{Map, Set, Value};
+use ordered_float::OrderedFloat;
+
+fn float(float: f64) -> Value {
+    Value::Float(OrderedFloat(float))
+}
+
+#[test]
+fn zeroes_are_one_key() {
+    let mut map = Map::new();
+    map.insert(float(-0.0), Value::from(1));
+    assert_eq!(map.insert(float(0.0), Value::from(2)), Some(Value::from(1)));
+    assert_eq!(map.get(&float(-0.0)), Some(&Value::from(2)));
+    assert_eq!(map.get(&Value::from(0)), Some(&Value::from(2)));
+    assert_eq!(map.len(), 1);
+    // the key stays the float first inserted
+    assert_eq!(map.to_string(), "{-0.0: 2}");
+}
+
+#[test]
+fn every_nan_is_one_key() {
+    let other_nan = f64::from_bits(f64::NAN.to_bits() ^ 1);
+    assert!(other_nan.is_nan());
+
+    let mut map = Map::new();
+    map.insert(float(f64::NAN), Value::from(1));
+    map.insert(float(-f64::NAN), Value::from(2));
+    assert_eq!(map.get(&float(other_nan)), Some(&Value::from(2)));
+    assert_eq!(map.len(), 1);
+
+    let set = [float(f64::NAN), float(other_nan), float(-f64::NAN)]
+        .into_iter()
+        .collect::<Set>();
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&float(f64::NAN)));
+}
+
+#[test]
+fn whole_floats_are_the_integer_key() {
+    let mut map = Map::new();
+    map.insert(Value::from(1), Value::from("int".to_string()));
+    map.insert(float(1.0), Value::from("float".to_string()));
+    map.insert(float(1.5), Value::from("half".to_string()));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&Value::from(1)), map.get(&float(1.0)));
+    assert_eq!(map.to_string(), "{1: \"float\", 1.5: \"half\"}");
+
+    let set = [Value::from(2), float(2.0), float(f64::INFINITY)]
+        .into_iter()
+        .collect::<Set>();
+    assert_eq!(set.len(), 2);
+}
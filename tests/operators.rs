@@ -0,0 +1,122 @@
+//! Applies every operator to a value of every type, checking that the interpreter never panics
+//! and that each error is of a kind that the operator can produce.
+use std::panic::{self, AssertUnwindSafe};
+
+use atium::{atium::Atium, error::describe, token::Type};
+use common::evaluate;
+
+mod common;
+
+/// Declares `examples`, which gives an expression producing a value of each type
+///
+/// The arms are also matched against [`Type`], so a new type fails to compile here until it is
+/// given an example.
+macro_rules! examples {
+    ($($(#[$attr:meta])* $ty:ident => $src:literal,)*) => {
+        fn examples() -> Vec<(&'static str, Type)> {
+            let _exhaustive = |ty: Type| match ty {
+                $($(#[$attr])* Type::$ty => (),)*
+            };
+            vec![$($(#[$attr])* ($src, Type::$ty),)*]
+        }
+    };
+}
+
+examples! {
+    String => "\"s\"",
+    Integer => "2",
+    Float => "1.5",
+    #[cfg(feature = "decimal")]
+    Decimal => "1.5d",
+    #[cfg(feature = "units")]
+    Quantity => "2m",
+    Boolean => "true",
+    Null => "nothing()",
+    Function => "len",
+    Class => "Point",
+    Trait => "Shape",
+    Instance => "Point()",
+    Ast => "quote { 1 }",
+    List => "\"ab\".chars()",
+    Range => "range(0, 2)",
+    Tuple => "(1, 2)",
+    Map => "bench(nothing, 1)",
+    Set => "set{1}",
+}
+
+/// Each unary operator, along with the kinds of error that it can produce
+const UNARY: [(&str, &[&str]); 2] = [("-", &["InvalidOperand"]), ("!", &["InvalidOperand"])];
+
+/// Each binary operator, along with the kinds of error that it can produce
+const BINARY: [(&str, &[&str]); 16] = [
+    ("+", &["InvalidTypes", "DimensionMismatch"]),
+    ("-", &["InvalidTypes", "DimensionMismatch"]),
+    ("*", &["InvalidTypes"]),
+    ("/", &["InvalidTypes"]),
+    ("==", &[]),
+    ("!=", &[]),
+    (">", &["InvalidTypes", "DimensionMismatch"]),
+    (">=", &["InvalidTypes", "DimensionMismatch"]),
+    ("<", &["InvalidTypes", "DimensionMismatch"]),
+    ("<=", &["InvalidTypes", "DimensionMismatch"]),
+    ("|", &["InvalidTypes"]),
+    ("&", &["InvalidTypes"]),
+    ("is", &["NotAClassOrTrait"]),
+    // the right is called with the left, so the errors of calling a function are possible too
+    ("|>", &["NotCallable", "ArityMismatch", "InvalidType"]),
+    // operators whose operands must be booleans, the right of which is only evaluated if needed
+    ("and", &["InvalidOperand"]),
+    ("or", &["InvalidOperand"]),
+];
+
+/// Declarations that the examples use
+const PRELUDE: &str = "fun nothing() {}\ntrait Shape {}\nclass Point {}\n";
+
+/// Runs `expr`, returning the kind of the first error that it produces
+fn run(expr: &str) -> Option<&'static str> {
+    let src = format!("{PRELUDE}{expr};");
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        Atium::new(&src, None)
+            .lex()
+            .and_then(Atium::parse)
+            .map_err(|errs| panic!("{expr} failed to parse: {errs:?}"))
+            .and_then(|atium| atium.interpret().map_err(|errs| describe(&errs[0]).kind))
+            .err()
+    }));
+
+    result.unwrap_or_else(|_| panic!("{expr} panicked"))
+}
+
+fn assert_kind(expr: &str, kinds: &[&str]) {
+    if let Some(kind) = run(expr) {
+        assert!(kinds.contains(&kind), "{expr} failed with {kind}");
+    }
+}
+
+#[test]
+fn examples_have_their_type() {
+    for (value, ty) in examples() {
+        let src = format!("{PRELUDE}typeof({value})");
+        assert_eq!(evaluate(&src, false), ty.to_string(), "{value}");
+    }
+}
+
+#[test]
+fn unary() {
+    for (op, kinds) in UNARY {
+        for (value, _) in examples() {
+            assert_kind(&format!("{op}({value})"), kinds);
+        }
+    }
+}
+
+#[test]
+fn binary() {
+    for (op, kinds) in BINARY {
+        for (left, _) in examples() {
+            for (right, _) in examples() {
+                assert_kind(&format!("({left}) {op} ({right})"), kinds);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Checks that `glob` finds files when the host grants the fs capability.
+use std::{env, fs, process};
+
+use common::evaluate;
+
+mod common;
+
+#[test]
+fn glob_finds_nested_files() {
+    let root = env::temp_dir().join(format!("atium-glob-{}", process::id()));
+    fs::create_dir_all(root.join("src/nested")).unwrap();
+    for file in ["src/main.at", "src/nested/lib.at", "src/notes.txt"] {
+        fs::write(root.join(file), "").unwrap();
+    }
+    let root = root.display().to_string().replace('\\', "/");
+
+    let found = evaluate(
+        &format!("glob(pathJoin(\"{root}\", \"src/**/*.at\"))"),
+        true,
+    );
+    assert_eq!(
+        found,
+        format!("[\"{root}/src/main.at\", \"{root}/src/nested/lib.at\"]")
+    );
+    assert_eq!(
+        evaluate(&format!("glob(\"{root}/src/*.txt\") |> len"), true),
+        "1"
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn glob_needs_the_fs_capability() {
+    assert_eq!(evaluate("glob(\"*.at\")", false), "CapabilityDenied");
+    assert_eq!(evaluate("glob(\"a/***\")", true), "InvalidArgument");
+}
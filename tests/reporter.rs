@@ -0,0 +1,58 @@
+//! Checks that diagnostics show the paths of files relative to the working directory they were
+//! given, or in full when asked to.
+use std::{env, fs, path::PathBuf, process};
+
+use atium::{atium::Atium, error::PathStyle, reporter};
+
+/// The first line of the location of the error that running a file called `file` produces
+fn location(file: &str, style: &PathStyle) -> String {
+    colored::control::set_override(false);
+    let errs = Atium::new("print 1 / 0;", Some(file))
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::evaluate)
+        .unwrap_err();
+    let rendered = reporter::render(&errs[0], style).to_string();
+    let line = rendered.lines().find(|line| line.contains("-->")).unwrap();
+    line.trim().trim_start_matches("--> ").to_string()
+}
+
+#[test]
+fn paths_are_relative_to_the_working_directory() {
+    let style = PathStyle {
+        absolute: false,
+        cwd: Some(PathBuf::from("/project")),
+    };
+    assert_eq!(location("/project/src/main.at", &style), "src/main.at:1:9");
+    assert_eq!(location("./src/main.at", &style), "src/main.at:1:9");
+    assert_eq!(
+        location("/elsewhere/main.at", &style),
+        "/elsewhere/main.at:1:9"
+    );
+    // sources that were not read from a file keep their names
+    assert_eq!(location("repl:1", &style), "repl:1:1:9");
+}
+
+#[test]
+fn paths_can_be_absolute() {
+    let cwd = env::temp_dir();
+    let file = format!("atium-reporter-{}.at", process::id());
+    fs::write(cwd.join(&file), "").unwrap();
+
+    let style = PathStyle {
+        absolute: true,
+        cwd: Some(cwd.clone()),
+    };
+    let expected = cwd.join(&file).display().to_string().replace('\\', "/");
+    assert_eq!(location(&file, &style), format!("{expected}:1:9"));
+
+    fs::remove_file(cwd.join(&file)).unwrap();
+}
+
+#[test]
+fn paths_are_verbatim_without_a_working_directory() {
+    assert_eq!(
+        location("src\\main.at", &PathStyle::VERBATIM),
+        "src/main.at:1:9"
+    );
+}
@@ -0,0 +1,56 @@
+//! Checks that classes must have each method of the traits they implement, declared the same way
+//! and accepting as many arguments, whether the trait is declared in the program or elsewhere.
+use atium::{
+    atium::{Atium, Options},
+    error::describe,
+};
+
+const SHAPE: &str = "trait Shape { area(factor); static unit(); get name(); set name(value); }";
+
+/// Runs `class` with `prelude`, returning the kind of the first error if there is one
+fn conforms(prelude: &str, class: &str) -> Option<String> {
+    Atium::new(class, None)
+        .with_options(Options {
+            prelude: Some(prelude.to_string()),
+            ..Options::default()
+        })
+        .lex()
+        .and_then(Atium::parse)
+        .and_then(Atium::interpret)
+        .err()
+        .map(|errs| describe(&errs[0]).kind.to_string())
+}
+
+#[test]
+fn classes_must_match_their_traits() {
+    let body = |area: &str, unit: &str| {
+        format!("{{ {area} {{}} {unit} {{}} get name() {{}} set name(value) {{}} }}")
+    };
+    let cases = [
+        (body("area(factor)", "static unit()"), None),
+        // defaults and rest parameters still accept the trait's arguments
+        (body("area(factor, by = 2)", "static unit(...rest)"), None),
+        (body("area()", "static unit()"), Some("MissingTraitMethod")),
+        (body("area(factor)", "unit()"), Some("MissingTraitMethod")),
+        (
+            body("static area(factor)", "static unit()"),
+            Some("MissingTraitMethod"),
+        ),
+    ];
+
+    for (body, expected) in cases {
+        let class = format!("class Square : Shape {body}");
+        // declared in the program, the trait is checked when parsing, and otherwise when the
+        // class is defined
+        let in_program = conforms("", &format!("{SHAPE} {class}"));
+        let in_prelude = conforms(SHAPE, &class);
+        assert_eq!(in_program.as_deref(), expected, "{class}");
+        assert_eq!(in_prelude.as_deref(), expected, "{class}");
+    }
+}
+
+#[test]
+fn trait_accessors_take_as_many_parameters_as_class_ones() {
+    let kind = conforms("", "trait Named { get name(value); }");
+    assert_eq!(kind.as_deref(), Some("AccessorParameters"));
+}